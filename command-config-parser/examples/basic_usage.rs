@@ -0,0 +1,18 @@
+use command_config_parser::{create_sample_config, CommandRegistry};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join("commands.json");
+    create_sample_config(&path)?;
+
+    let registry = CommandRegistry::load_from_file(&path)?;
+
+    // Look a command up by name or alias
+    if let Some(command) = registry.get_command("d") {
+        println!("d → {} {}", command.executable, command.args.join(" "));
+    }
+
+    // Autocomplete
+    println!("completions for 'b': {:?}", registry.get_completions("b"));
+
+    Ok(())
+}
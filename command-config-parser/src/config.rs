@@ -1,33 +1,213 @@
 // config.rs
+use crate::danger::DangerLevel;
+use crate::diagnostic::{Diagnostic, Span};
+use crate::engines::{load_engines, SearchEngine};
+use crate::env::{check_vars, EnvMode};
+use crate::exec::ExecMode;
+use crate::format::ConfigFormat;
+use crate::hotkey::{HotkeyAction, HotkeyBinding, HotkeySpec, SequenceBinding};
+use crate::fuzzy::{fuzzy_match, FuzzyMatch};
+use crate::index::PrefixIndex;
+use crate::migrate::CURRENT_VERSION;
+use crate::params::{placeholders, Param, ParamKind, BUILTIN_PLACEHOLDERS};
+use crate::search::{candidate_paths, config_layers};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Command {
+    /// Unique name the command is run by.
+    #[schemars(pattern(crate::schema::NON_BLANK))]
     pub name: String,
+    /// Shown next to the command in the launcher.
     pub description: String,
+    /// Program to run, looked up on `PATH` unless it is a path. `~` and
+    /// variables in it are expanded; with a shell `exec` it starts the
+    /// script and is left for the shell. Never takes placeholders.
+    #[schemars(pattern(crate::schema::NON_BLANK))]
     pub executable: String,
+    /// Arguments passed to the executable, one per entry.
     pub args: Vec<String>,
+    /// Start the executable directly, or run the command line in a shell.
+    #[serde(default, skip_serializing_if = "ExecMode::is_direct")]
+    pub exec: ExecMode,
+    /// Directory to run the command in.
     pub working_dir: Option<String>,
+    /// Extra environment variables for the command.
     pub env_vars: Option<HashMap<String, String>>,
+    /// How `env_vars` combine with the launcher's environment.
+    #[serde(default, skip_serializing_if = "EnvMode::is_inherit")]
+    pub env_mode: EnvMode,
+    /// Other names the command can be run by.
+    #[serde(default)]
+    #[schemars(schema_with = "crate::schema::aliases")]
     pub aliases: Option<Vec<String>>,
+    /// Group the command is listed under.
+    #[serde(default)]
+    #[schemars(schema_with = "crate::schema::category")]
     pub category: Option<String>,
+    /// Show the command's output when it finishes, not only on failure.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub show_output: bool,
+    /// Seconds the command may run before it is stopped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 1))]
+    pub timeout: Option<u64>,
+    /// Signal sent to the command's process group to stop it, on timeout
+    /// or when cancelled. Windows ends the whole job instead.
+    #[serde(default, skip_serializing_if = "KillSignal::is_default")]
+    pub kill_signal: KillSignal,
+    /// Ask before running, showing the full command line.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub confirm: bool,
+    /// How risky the command is; `medium` and `high` also ask before
+    /// running.
+    #[serde(default, skip_serializing_if = "DangerLevel::is_low")]
+    pub danger_level: DangerLevel,
+    /// Values the command asks for, used as `{name}` placeholders.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<Param>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A command configuration file.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct CommandConfig {
+    /// Schema version the file is written in.
     pub version: String,
+    /// Prefix for every command in this file, e.g. `git` for `git:status`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Other command files or glob patterns, relative to this file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<Include>,
+    /// Global hotkey that opens the launcher, e.g. `Ctrl+Shift+Space`,
+    /// `Super+P` or `F13`. Defaults to `Ctrl+Space`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub hotkey: Option<HotkeySpec>,
+    /// More hotkeys, each bound to its own action.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hotkeys: Vec<HotkeyBinding>,
+    /// Key sequences such as `Ctrl+Space g s`: a leader hotkey, then
+    /// plain keys typed one after another.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sequences: Vec<SequenceBinding>,
+    /// How long to wait for the next key of a sequence, in milliseconds.
+    /// Defaults to 1000.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence_timeout_ms: Option<u64>,
+    /// Text to copy from the clipboard view.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snippets: Vec<Snippet>,
+    /// Web searches run by a keyword, e.g. `gh totalcontrol`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub engines: Vec<SearchEngine>,
+    /// TOML or JSON files listing more search engines, relative to this
+    /// file. Engines in this file replace imported ones with the same
+    /// keyword.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub import_engines: Vec<String>,
+    #[serde(default)]
     pub commands: Vec<Command>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Snippet {
+    /// Shown and searched in the clipboard view.
+    #[schemars(pattern(crate::schema::NON_BLANK))]
+    pub name: String,
+    /// Copied to the clipboard when picked.
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Include {
+    Path(String),
+    /// A command pack, mounted under `namespace` instead of its own.
+    Pack {
+        path: String,
+        namespace: Option<String>,
+    },
+}
+
+/// Signal used to stop a running command.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum KillSignal {
+    #[default]
+    #[serde(rename = "SIGTERM", alias = "TERM")]
+    Term,
+    #[serde(rename = "SIGINT", alias = "INT")]
+    Int,
+    #[serde(rename = "SIGHUP", alias = "HUP")]
+    Hup,
+    #[serde(rename = "SIGKILL", alias = "KILL")]
+    Kill,
+}
+
+impl KillSignal {
+    pub fn is_default(&self) -> bool {
+        *self == KillSignal::default()
+    }
+}
+
+impl Include {
+    pub fn path(&self) -> &str {
+        match self {
+            Include::Path(path) | Include::Pack { path, .. } => path,
+        }
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        match self {
+            Include::Path(_) => None,
+            Include::Pack { namespace, .. } => namespace.as_deref(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ConfigError {
     FileNotFound,
     ParseError(String),
+    // A command whose name or aliases clash with one already registered
+    Conflict(String),
     IoError(std::io::Error),
     JsonError(serde_json::Error),
+    SerializeError(String),
+    // Every problem found in a configuration file, with locations
+    Invalid(Vec<Diagnostic>),
+}
+
+impl ConfigError {
+    // Attribute located problems to the file they were found in
+    pub fn with_path<P: AsRef<Path>>(self, path: P) -> Self {
+        match self {
+            ConfigError::Invalid(diagnostics) => ConfigError::Invalid(
+                diagnostics
+                    .into_iter()
+                    .map(|diagnostic| diagnostic.with_path(&path))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    // Multi-line form with source snippets, for logs and the CLI
+    pub fn render(&self) -> String {
+        match self {
+            ConfigError::Invalid(diagnostics) => diagnostics
+                .iter()
+                .map(Diagnostic::render)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            other => format!("error: {}\n", other),
+        }
+    }
 }
 
 impl std::fmt::Display for ConfigError {
@@ -35,8 +215,15 @@ impl std::fmt::Display for ConfigError {
         match self {
             ConfigError::FileNotFound => write!(f, "Configuration file not found"),
             ConfigError::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            ConfigError::Conflict(msg) => write!(f, "{}", msg),
             ConfigError::IoError(err) => write!(f, "IO error: {}", err),
             ConfigError::JsonError(err) => write!(f, "JSON error: {}", err),
+            ConfigError::SerializeError(msg) => write!(f, "Serialize error: {}", msg),
+            ConfigError::Invalid(diagnostics) => match diagnostics.as_slice() {
+                [] => write!(f, "Invalid configuration"),
+                [only] => write!(f, "{}", only),
+                [first, rest @ ..] => write!(f, "{} (and {} more problems)", first, rest.len()),
+            },
         }
     }
 }
@@ -55,73 +242,365 @@ impl From<serde_json::Error> for ConfigError {
     }
 }
 
+// Looked up in this order in every configuration directory
+pub const DEFAULT_CONFIG_FILES: &[&str] = &[
+    "commands.json",
+    "commands.jsonc",
+    "commands.json5",
+    "commands.toml",
+    "commands.yaml",
+    "commands.yml",
+];
+
+// How long a key sequence waits for its next key unless configured
+pub const DEFAULT_SEQUENCE_TIMEOUT_MS: u64 = 1000;
+
+#[derive(Default)]
 pub struct CommandRegistry {
     commands: Vec<Command>,
     name_map: HashMap<String, usize>,
     alias_map: HashMap<String, usize>,
+    index: PrefixIndex,
+    // Files the commands were loaded from, lowest priority first
+    sources: Vec<PathBuf>,
+    // Which of `sources` each command came from, parallel to `commands`
+    origins: Vec<Option<usize>>,
+    // Directory layer discovery started from, so reloads find new files
+    search_root: Option<PathBuf>,
+    // Set by the highest priority file that has one
+    hotkey: Option<HotkeySpec>,
+    // A later file's binding replaces an earlier one for the same keys
+    hotkeys: Vec<HotkeyBinding>,
+    sequences: Vec<SequenceBinding>,
+    sequence_timeout_ms: Option<u64>,
+    snippets: Vec<Snippet>,
+    // A later engine replaces an earlier one with the same keyword
+    engines: Vec<SearchEngine>,
+    // Engine lists imported by the sources, watched along with them
+    engine_files: Vec<PathBuf>,
+    // Directories searched by include patterns, watched for new matches
+    include_dirs: Vec<PathBuf>,
+    // Problems that did not stop the configuration from loading
+    warnings: Vec<Diagnostic>,
 }
 
 impl CommandRegistry {
     pub fn new() -> Self {
-        Self {
-            commands: Vec::new(),
-            name_map: HashMap::new(),
-            alias_map: HashMap::new(),
-        }
+        Self::default()
     }
 
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
-        if !path.as_ref().exists() {
-            return Err(ConfigError::FileNotFound);
+        Self::load_layered(&[path])
+    }
+
+    /// Load and merge several configuration files, lowest priority first.
+    ///
+    /// A command in a later file replaces an earlier command with the same
+    /// name, and takes over any alias an earlier command used for itself.
+    /// Reusing an earlier command's name as an alias is an error, since
+    /// names always win lookups.
+    pub fn load_layered<P: AsRef<Path>>(paths: &[P]) -> Result<Self, ConfigError> {
+        // Expand includes first; included files load before their includer
+        let mut loader = Loader::default();
+        for path in paths {
+            loader.collect(path.as_ref(), None, None, true)?;
         }
 
-        let content = fs::read_to_string(path)?;
-        let config: CommandConfig = serde_json::from_str(&content)?;
-        
         let mut registry = Self::new();
-        registry.load_commands(config.commands)?;
-        
+        let mut problems = loader.problems;
+        registry.include_dirs = loader.include_dirs;
+
+        for file in loader.files {
+            let origin = registry.sources.len();
+            registry.sources.push(file.path.clone());
+            registry.hotkey = file.hotkey.or(registry.hotkey);
+            for binding in file.hotkeys {
+                registry.hotkeys.retain(|earlier| earlier.keys != binding.keys);
+                registry.hotkeys.push(binding);
+            }
+            for binding in file.sequences {
+                registry.sequences.retain(|earlier| earlier.keys != binding.keys);
+                registry.sequences.push(binding);
+            }
+            registry.sequence_timeout_ms = file.sequence_timeout_ms.or(registry.sequence_timeout_ms);
+            registry.snippets.extend(file.snippets);
+            for engine in file.engines {
+                registry.engines.retain(|earlier| earlier.keyword != engine.keyword);
+                if let Some(earlier) = registry.engines.iter().find(|earlier| earlier.default && engine.default) {
+                    registry.warnings.push(
+                        Diagnostic::new(format!(
+                            "`{}` and `{}` are both the default search engine; `{}` is used",
+                            earlier.keyword, engine.keyword, engine.keyword
+                        ))
+                        .with_path(&file.path)
+                        .with_hint("the last default wins; remove `default` from the other"),
+                    );
+                }
+                registry.engines.push(engine);
+            }
+            registry.engine_files.extend(file.engine_files);
+
+            for command in file.commands {
+                let command = match file.namespace {
+                    Some(ref namespace) => namespaced(command, namespace),
+                    None => command,
+                };
+                let name = command.name.clone();
+                if let Err(err) = registry.merge_command(command, origin) {
+                    problems.push(
+                        Diagnostic::new(format!("cannot merge command `{}` into the earlier layers: {}", name, err))
+                            .with_path(&file.path),
+                    );
+                }
+            }
+        }
+
+        // Commands may come from any layer, so bindings are checked last
+        for binding in &registry.hotkeys {
+            if let HotkeyAction::Command(ref name) = binding.action {
+                if registry.get_command(name).is_none() {
+                    problems.push(Diagnostic::new(format!(
+                        "hotkey `{}` runs unknown command `{}`", binding.keys, name
+                    )));
+                }
+            }
+        }
+        problems.extend(registry.check_sequences());
+
+        if !problems.is_empty() {
+            return Err(ConfigError::Invalid(problems));
+        }
+
+        Ok(registry)
+    }
+
+    // The system, user and project layers found from `cwd`
+    pub fn load_from_dir<P: AsRef<Path>>(cwd: P) -> Result<Self, ConfigError> {
+        let layers = config_layers(cwd.as_ref());
+        if layers.is_empty() {
+            return Err(ConfigError::FileNotFound);
+        }
+
+        let mut registry = Self::load_layered(&layers)?;
+        registry.search_root = Some(cwd.as_ref().to_path_buf());
         Ok(registry)
     }
 
     pub fn load_from_default() -> Result<Self, ConfigError> {
-        Self::load_from_file("commands.json")
+        Self::load_from_dir(std::env::current_dir()?)
     }
 
-    fn load_commands(&mut self, commands: Vec<Command>) -> Result<(), ConfigError> {
-        self.commands.clear();
-        self.name_map.clear();
-        self.alias_map.clear();
+    // Parse the source files again into a fresh registry. The current one
+    // is left untouched, so callers only swap it out if this succeeds.
+    pub fn reload(&self) -> Result<Self, ConfigError> {
+        match self.search_root {
+            Some(ref root) => Self::load_from_dir(root),
+            None if self.sources.is_empty() => Err(ConfigError::FileNotFound),
+            None => Self::load_layered(&self.sources),
+        }
+    }
 
-        for (index, command) in commands.into_iter().enumerate() {
-            // Check for duplicate command names
-            if self.name_map.contains_key(&command.name) {
-                return Err(ConfigError::ParseError(format!(
-                    "Duplicate command name: {}", command.name
-                )));
+    pub fn sources(&self) -> &[PathBuf] {
+        &self.sources
+    }
+
+    // The launcher hotkey from the highest priority file that sets one
+    pub fn hotkey(&self) -> HotkeySpec {
+        self.hotkey.unwrap_or_default()
+    }
+
+    // Every hotkey to register: the launcher's, then the configured ones.
+    // A configured binding for the same keys takes its place.
+    pub fn hotkey_bindings(&self) -> Vec<HotkeyBinding> {
+        let keys = self.hotkey();
+        let mut bindings = Vec::new();
+        if self.hotkeys.iter().all(|binding| binding.keys != keys) {
+            bindings.push(HotkeyBinding {
+                keys,
+                action: HotkeyAction::Launcher,
+            });
+        }
+        bindings.extend(self.hotkeys.iter().cloned());
+
+        // A leader with no binding of its own just opens the launcher, where
+        // the rest of the sequence is typed
+        for sequence in &self.sequences {
+            let leader = sequence.keys.leader;
+            if bindings.iter().all(|binding| binding.keys != leader) {
+                bindings.push(HotkeyBinding {
+                    keys: leader,
+                    action: HotkeyAction::Launcher,
+                });
             }
+        }
+        bindings
+    }
+
+    pub fn sequences(&self) -> &[SequenceBinding] {
+        &self.sequences
+    }
 
-            // Register command name
-            self.name_map.insert(command.name.clone(), index);
-
-            // Register aliases
-            if let Some(ref aliases) = command.aliases {
-                for alias in aliases {
-                    if self.alias_map.contains_key(alias) || self.name_map.contains_key(alias) {
-                        return Err(ConfigError::ParseError(format!(
-                            "Duplicate alias or command name: {}", alias
-                        )));
-                    }
-                    self.alias_map.insert(alias.clone(), index);
+    pub fn sequence_timeout(&self) -> Duration {
+        Duration::from_millis(self.sequence_timeout_ms.unwrap_or(DEFAULT_SEQUENCE_TIMEOUT_MS))
+    }
+
+    // Sequences must run known commands, start from a leader that opens the
+    // launcher, and not end where another one goes on
+    fn check_sequences(&self) -> Vec<Diagnostic> {
+        let mut problems = Vec::new();
+        let bindings = self.hotkey_bindings();
+        for (i, binding) in self.sequences.iter().enumerate() {
+            let keys = &binding.keys;
+            if let HotkeyAction::Command(ref name) = binding.action {
+                if self.get_command(name).is_none() {
+                    problems.push(Diagnostic::new(format!("sequence `{}` runs unknown command `{}`", keys, name)));
                 }
             }
+            let leader = bindings.iter().find(|hotkey| hotkey.keys == keys.leader);
+            if let Some(HotkeyBinding { action: HotkeyAction::Command(ref name), .. }) = leader {
+                problems.push(
+                    Diagnostic::new(format!("sequence `{}` starts with a hotkey that runs `{}`", keys, name))
+                        .with_hint("a leader has to open the launcher so the rest of the sequence can be typed"),
+                );
+            }
+            if let Some(longer) = self.sequences[i + 1..]
+                .iter()
+                .chain(&self.sequences[..i])
+                .find(|other| keys.is_prefix_of(&other.keys))
+            {
+                problems.push(
+                    Diagnostic::new(format!("sequence `{}` is the start of `{}`", keys, longer.keys))
+                        .with_hint("the longer one could never be typed; make them differ before the shorter one ends"),
+                );
+            }
+        }
+        problems
+    }
+
+    pub fn snippets(&self) -> &[Snippet] {
+        &self.snippets
+    }
+
+    pub fn engines(&self) -> &[SearchEngine] {
+        &self.engines
+    }
+
+    /// Problems that did not stop the configuration from loading, such as
+    /// two files each naming a default search engine.
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    // The engine for queries nothing else matches; the last one marked
+    // default, so a higher priority file can pick another
+    pub fn default_engine(&self) -> Option<&SearchEngine> {
+        self.engines.iter().rev().find(|engine| engine.default)
+    }
+
+    // Files to watch for changes, including layers that may appear later
+    pub fn watch_paths(&self) -> Vec<PathBuf> {
+        let mut paths = match self.search_root {
+            Some(ref root) => candidate_paths(root),
+            None => Vec::new(),
+        };
+        for source in self.sources.iter().chain(&self.engine_files).chain(&self.include_dirs) {
+            if !paths.contains(source) {
+                paths.push(source.clone());
+            }
+        }
+        paths
+    }
+
+    // The configuration file a command (or alias) was loaded from
+    pub fn source_of(&self, name: &str) -> Option<&Path> {
+        let index = self.name_map.get(name).or_else(|| self.alias_map.get(name))?;
+        let origin = self.origins[*index]?;
+        self.sources.get(origin).map(PathBuf::as_path)
+    }
+
+    pub fn add_command(&mut self, command: Command) -> Result<(), ConfigError> {
+        self.insert_command(command, None)
+    }
+
+    fn merge_command(&mut self, command: Command, origin: usize) -> Result<(), ConfigError> {
+        self.remove_command(&command.name);
 
-            self.commands.push(command);
+        let keys = std::iter::once(&command.name).chain(command.aliases.iter().flatten());
+        for key in keys {
+            if let Some(&owner) = self.alias_map.get(key) {
+                self.drop_alias(owner, key);
+            }
+        }
+
+        self.insert_command(command, Some(origin))
+    }
+
+    fn insert_command(&mut self, command: Command, origin: Option<usize>) -> Result<(), ConfigError> {
+        // Check the name and aliases before registering anything
+        if let Some(owner) = self.get_command(&command.name) {
+            return Err(ConfigError::Conflict(format!(
+                "command name `{}` is already used by command `{}`", command.name, owner.name
+            )));
         }
 
+        let mut keys = vec![&command.name];
+        for alias in command.aliases.iter().flatten() {
+            if let Some(owner) = self.get_command(alias) {
+                return Err(ConfigError::Conflict(format!(
+                    "alias `{}` is already used by command `{}`", alias, owner.name
+                )));
+            }
+            if keys.contains(&alias) {
+                return Err(ConfigError::Conflict(format!(
+                    "alias `{}` is repeated in command `{}`", alias, command.name
+                )));
+            }
+            keys.push(alias);
+        }
+
+        let index = self.commands.len();
+        self.name_map.insert(command.name.clone(), index);
+        self.index.insert(&command.name);
+
+        for alias in command.aliases.iter().flatten() {
+            self.alias_map.insert(alias.clone(), index);
+            self.index.insert(alias);
+        }
+
+        self.commands.push(command);
+        self.origins.push(origin);
         Ok(())
     }
 
+    pub fn remove_command(&mut self, name: &str) -> Option<Command> {
+        let index = self.name_map.remove(name)?;
+        let command = self.commands.remove(index);
+        self.origins.remove(index);
+
+        self.index.remove(&command.name);
+        for alias in command.aliases.iter().flatten() {
+            self.alias_map.remove(alias);
+            self.index.remove(alias);
+        }
+
+        // Later commands shift down by one
+        for position in self.name_map.values_mut().chain(self.alias_map.values_mut()) {
+            if *position > index {
+                *position -= 1;
+            }
+        }
+
+        Some(command)
+    }
+
+    fn drop_alias(&mut self, index: usize, alias: &str) {
+        self.alias_map.remove(alias);
+        self.index.remove(alias);
+        if let Some(ref mut aliases) = self.commands[index].aliases {
+            aliases.retain(|a| a != alias);
+        }
+    }
+
     pub fn get_command(&self, name: &str) -> Option<&Command> {
         // First try direct name lookup
         if let Some(&index) = self.name_map.get(name) {
@@ -144,25 +623,61 @@ impl CommandRegistry {
         self.name_map.keys().collect()
     }
 
-    pub fn get_completions(&self, prefix: &str) -> Vec<String> {
-        let mut completions = Vec::new();
-        
-        // Add matching command names
-        for name in self.name_map.keys() {
-            if name.starts_with(prefix) {
-                completions.push(name.clone());
+    pub fn index(&self) -> &PrefixIndex {
+        &self.index
+    }
+
+    pub fn get_completions(&self, query: &str) -> Vec<String> {
+        // Fuzzy-score the index candidates, best match first
+        let mut completions: Vec<(i64, &str)> = self
+            .index
+            .fuzzy(query)
+            .into_iter()
+            .filter_map(|key| fuzzy_match(query, key).map(|m| (m.score, key)))
+            .collect();
+
+        completions.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        completions.into_iter().map(|(_, key)| key.to_string()).collect()
+    }
+
+    // Commands matching `query` by name or alias with their best match, in
+    // declaration order. Only a match on the name carries highlight ranges.
+    pub fn search(&self, query: &str) -> Vec<(&Command, FuzzyMatch)> {
+        self.search_keys(query).into_iter().map(|(command, _, m)| (command, m)).collect()
+    }
+
+    // Like `search`, with the name or alias that gave each best match
+    pub fn search_keys(&self, query: &str) -> Vec<(&Command, &str, FuzzyMatch)> {
+        let mut best: HashMap<usize, (&str, FuzzyMatch)> = HashMap::new();
+
+        for key in self.index.fuzzy(query) {
+            // Keys the maps no longer know are skipped rather than trusted
+            let (index, is_name) = match (self.name_map.get(key), self.alias_map.get(key)) {
+                (Some(&index), _) => (index, true),
+                (None, Some(&index)) => (index, false),
+                (None, None) => continue,
+            };
+            let Some(mut m) = fuzzy_match(query, key) else {
+                continue;
+            };
+            if !is_name {
+                m.ranges.clear();
             }
-        }
-        
-        // Add matching aliases
-        for alias in self.alias_map.keys() {
-            if alias.starts_with(prefix) {
-                completions.push(alias.clone());
+
+            match best.get(&index) {
+                Some((_, current)) if current.score >= m.score => {}
+                _ => {
+                    best.insert(index, (key, m));
+                }
             }
         }
-        
-        completions.sort();
-        completions
+
+        let mut results: Vec<(usize, (&str, FuzzyMatch))> = best.into_iter().collect();
+        results.sort_by_key(|(index, _)| *index);
+        results
+            .into_iter()
+            .filter_map(|(index, (key, m))| Some((self.commands.get(index)?, key, m)))
+            .collect()
     }
 
     pub fn get_commands_by_category(&self, category: &str) -> Vec<&Command> {
@@ -171,7 +686,7 @@ impl CommandRegistry {
             .filter(|cmd| {
                 cmd.category
                     .as_ref()
-                    .map_or(false, |cat| cat == category)
+                    .is_some_and(|cat| cat == category)
             })
             .collect()
     }
@@ -189,50 +704,387 @@ impl CommandRegistry {
     }
 }
 
-// Helper function to create a sample configuration file
+// One configuration file to merge, after include expansion
+struct LoadedFile {
+    path: PathBuf,
+    namespace: Option<String>,
+    hotkey: Option<HotkeySpec>,
+    hotkeys: Vec<HotkeyBinding>,
+    sequences: Vec<SequenceBinding>,
+    sequence_timeout_ms: Option<u64>,
+    snippets: Vec<Snippet>,
+    // Imported engines first, then the file's own
+    engines: Vec<SearchEngine>,
+    engine_files: Vec<PathBuf>,
+    commands: Vec<Command>,
+}
+
+#[derive(Default)]
+struct Loader {
+    files: Vec<LoadedFile>,
+    problems: Vec<Diagnostic>,
+    // Files currently being expanded, for cycle detection
+    stack: Vec<PathBuf>,
+    // Files already loaded, so one included twice loads once
+    loaded: HashSet<PathBuf>,
+    include_dirs: Vec<PathBuf>,
+}
+
+impl Loader {
+    fn collect(
+        &mut self,
+        path: &Path,
+        parent_namespace: Option<&str>,
+        include_namespace: Option<&str>,
+        top_level: bool,
+    ) -> Result<(), ConfigError> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if let Some(start) = self.stack.iter().position(|p| *p == canonical) {
+            let cycle: Vec<String> = self.stack[start..]
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect();
+            self.problems.push(
+                Diagnostic::new("include cycle")
+                    .with_path(path)
+                    .with_hint(cycle.join(" → ")),
+            );
+            return Ok(());
+        }
+        if self.loaded.contains(&canonical) {
+            return Ok(());
+        }
+
+        let config = match read_config(path) {
+            Ok(config) => config,
+            Err(ConfigError::FileNotFound) if !top_level => {
+                self.problems.push(Diagnostic::new("included file not found").with_path(path));
+                return Ok(());
+            }
+            Err(ConfigError::Invalid(diagnostics)) => {
+                self.problems.extend(diagnostics);
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+
+        let own = include_namespace.or(config.namespace.as_deref());
+        let namespace = match (parent_namespace, own) {
+            (Some(parent), Some(own)) => Some(format!("{}:{}", parent, own)),
+            (parent, own) => parent.or(own).map(str::to_string),
+        };
+
+        self.stack.push(canonical);
+        let base = path.parent().unwrap_or(Path::new(""));
+        for include in &config.include {
+            if let Some(dir) = pattern_dir(base, include.path()) {
+                if !self.include_dirs.contains(&dir) {
+                    self.include_dirs.push(dir);
+                }
+            }
+            for included in expand_include(base, include.path(), path, &mut self.problems) {
+                self.collect(&included, namespace.as_deref(), include.namespace(), false)?;
+            }
+        }
+        if let Some(canonical) = self.stack.pop() {
+            self.loaded.insert(canonical);
+        }
+
+        let mut engines = Vec::new();
+        let mut engine_files = Vec::new();
+        for import in &config.import_engines {
+            let imported = base.join(import);
+            match load_engines(&imported) {
+                Ok(list) => engines.extend(list),
+                Err(ConfigError::FileNotFound) => {
+                    self.problems.push(Diagnostic::new("imported engine list not found").with_path(&imported));
+                }
+                Err(ConfigError::Invalid(diagnostics)) => self.problems.extend(diagnostics),
+                Err(err) => self.problems.push(Diagnostic::new(err.to_string()).with_path(&imported)),
+            }
+            engine_files.push(imported);
+        }
+        for engine in config.engines {
+            engines.retain(|imported: &SearchEngine| imported.keyword != engine.keyword);
+            engines.push(engine);
+        }
+
+        self.files.push(LoadedFile {
+            path: path.to_path_buf(),
+            namespace,
+            hotkey: config.hotkey,
+            hotkeys: config.hotkeys,
+            sequences: config.sequences,
+            sequence_timeout_ms: config.sequence_timeout_ms,
+            snippets: config.snippets,
+            engines,
+            engine_files,
+            commands: config.commands,
+        });
+        Ok(())
+    }
+}
+
+// Resolve an include entry relative to the including file. Glob patterns
+// may match nothing; a plain path that does not exist is reported later.
+fn expand_include(base: &Path, pattern: &str, includer: &Path, problems: &mut Vec<Diagnostic>) -> Vec<PathBuf> {
+    let joined = base.join(pattern);
+    if !pattern.contains(['*', '?', '[']) {
+        return vec![joined];
+    }
+
+    match glob::glob(&joined.to_string_lossy()) {
+        Ok(paths) => {
+            let mut matched: Vec<PathBuf> = paths.filter_map(Result::ok).collect();
+            matched.sort();
+            matched
+        }
+        Err(err) => {
+            problems.push(
+                Diagnostic::new(format!("invalid include pattern `{}`", pattern))
+                    .with_path(includer)
+                    .with_hint(err.msg),
+            );
+            Vec::new()
+        }
+    }
+}
+
+// The directory a glob pattern searches, up to its first wildcard; files
+// created there may start matching it
+fn pattern_dir(base: &Path, pattern: &str) -> Option<PathBuf> {
+    if !pattern.contains(['*', '?', '[']) {
+        return None;
+    }
+    let fixed = Path::new(pattern)
+        .components()
+        .take_while(|component| !component.as_os_str().to_string_lossy().contains(['*', '?', '[']));
+    Some(base.join(fixed.collect::<PathBuf>()))
+}
+
+// Prefix a pack's command name and aliases with its namespace
+fn namespaced(mut command: Command, namespace: &str) -> Command {
+    command.name = format!("{}:{}", namespace, command.name);
+    if let Some(ref mut aliases) = command.aliases {
+        for alias in aliases {
+            *alias = format!("{}:{}", namespace, alias);
+        }
+    }
+    command
+}
+
+// Parse and validate one configuration file
+fn read_config(path: &Path) -> Result<CommandConfig, ConfigError> {
+    if !path.exists() {
+        return Err(ConfigError::FileNotFound);
+    }
+
+    let content = fs::read_to_string(path)?;
+    ConfigFormat::from_path(path)
+        .parse(&content)
+        .map_err(|err| err.with_path(path))
+}
+
+// Check names and aliases across all commands; the schema covers each
+// command on its own. `locate` maps a key such as `commands[2].aliases[0]`
+// to its position in the file.
+pub fn validate(commands: &[Command], locate: impl Fn(&str) -> Option<Span>) -> Vec<Diagnostic> {
+    let mut problems = Vec::new();
+    // Every name and alias seen so far, with the index of the command
+    // defining it and the key it is defined at
+    let mut seen: HashMap<&str, (usize, String)> = HashMap::new();
+
+    let at_line = |key: &str| {
+        locate(key)
+            .map(|span| format!(" at line {}", span.line))
+            .unwrap_or_default()
+    };
+
+    for (index, command) in commands.iter().enumerate() {
+        let name_key = format!("commands[{}].name", index);
+
+        let aliases = command.aliases.iter().flatten().enumerate();
+        let keys = std::iter::once((name_key, "command name", &command.name)).chain(
+            aliases.map(|(i, alias)| (format!("commands[{}].aliases[{}]", index, i), "alias", alias)),
+        );
+
+        for (key, kind, value) in keys {
+            if value.is_empty() {
+                continue;
+            }
+
+            match seen.get(value.as_str()) {
+                Some(&(owner, ref owner_key)) => {
+                    let owner_name = &commands[owner].name;
+                    let hint = if owner == index {
+                        format!("`{}` is already used by this command{}", value, at_line(owner_key))
+                    } else if *owner_name == command.name {
+                        // Two commands with one name; only the position tells them apart
+                        format!(
+                            "`{}` is already used by the other command named `{}`, commands[{}]{}",
+                            value, owner_name, owner, at_line(owner_key)
+                        )
+                    } else {
+                        format!("`{}` is already used by command `{}`{}", value, owner_name, at_line(owner_key))
+                    };
+                    problems.push(located(
+                        Diagnostic::new(format!("duplicate {} `{}`", kind, value))
+                            .with_key(key)
+                            .with_hint(hint),
+                        &locate,
+                    ));
+                }
+                None => {
+                    seen.insert(value, (index, key));
+                }
+            }
+        }
+
+        validate_params(index, command, &mut problems, &locate);
+    }
+
+    problems
+}
+
+// Variable references and parameter declarations
+fn validate_params(
+    index: usize,
+    command: &Command,
+    problems: &mut Vec<Diagnostic>,
+    locate: &impl Fn(&str) -> Option<Span>,
+) {
+    // A shell expands variables in its own script
+    let fields = std::iter::once(("executable".to_string(), command.executable.as_str()))
+        .chain(command.templates())
+        .filter(|(field, _)| command.exec.is_direct() || !(field == "executable" || field.starts_with("args[")));
+    for (field, text) in fields {
+        if let Err(message) = check_vars(text) {
+            let key = format!("commands[{}].{}", index, field);
+            problems.push(located(Diagnostic::new(message).with_key(key), locate));
+        }
+    }
+
+    // Quotes may open in one argument and close in another, so the script
+    // is read as a whole
+    let script = command.exec.script(&command.executable, &command.args);
+    for placeholder in command.exec.quoted_placeholders(&script) {
+        let arg = command.args.iter().position(|arg| placeholders(arg).contains(&placeholder)).unwrap_or(0);
+        let key = format!("commands[{}].args[{}]", index, arg);
+        let problem = Diagnostic::new(format!("placeholder `{{{}}}` is inside quotes", placeholder.name))
+            .with_key(key)
+            .with_hint("values are quoted for the shell as they are put in; remove the quotes around it");
+        problems.push(located(problem, locate));
+    }
+
+    let mut names = Vec::new();
+    for (i, param) in command.params.iter().enumerate() {
+        let key = format!("commands[{}].params[{}]", index, i);
+        let problem = if BUILTIN_PLACEHOLDERS.contains(&param.name.as_str()) {
+            Some(Diagnostic::new(format!("parameter `{}` shadows a built-in placeholder", param.name))
+                .with_hint(format!("built-in placeholders are {}", BUILTIN_PLACEHOLDERS.join(", "))))
+        } else if names.contains(&&param.name) {
+            Some(Diagnostic::new(format!("duplicate parameter `{}`", param.name)))
+        } else if param.kind == ParamKind::Choice && param.choices.is_empty() {
+            Some(Diagnostic::new(format!("choice parameter `{}` has no choices", param.name)))
+        } else {
+            // Relative path defaults are only resolved when used
+            let default = param.default.as_deref().filter(|_| param.kind != ParamKind::Path);
+            default
+                .and_then(|default| param.check(default).err())
+                .map(|message| Diagnostic::new(format!("invalid default: {}", message)))
+        };
+
+        if let Some(problem) = problem {
+            problems.push(located(problem.with_key(key), locate));
+        }
+        names.push(&param.name);
+    }
+}
+
+fn located(mut diagnostic: Diagnostic, locate: &impl Fn(&str) -> Option<Span>) -> Diagnostic {
+    diagnostic.span = diagnostic.key.as_deref().and_then(locate);
+    diagnostic
+}
+
+// Helper function to create a sample configuration file, in the format
+// matching the file extension
 pub fn create_sample_config<P: AsRef<Path>>(path: P) -> Result<(), ConfigError> {
     let sample_config = CommandConfig {
-        version: "1.0".to_string(),
+        version: CURRENT_VERSION.to_string(),
+        namespace: None,
+        include: Vec::new(),
+        hotkey: None,
+        hotkeys: Vec::new(),
+        sequences: Vec::new(),
+        sequence_timeout_ms: None,
+        snippets: Vec::new(),
+        engines: Vec::new(),
+        import_engines: Vec::new(),
         commands: vec![
             Command {
                 name: "build".to_string(),
                 description: "Build the project".to_string(),
                 executable: "cargo".to_string(),
                 args: vec!["build".to_string()],
+                exec: ExecMode::Direct,
                 working_dir: None,
                 env_vars: None,
+                env_mode: EnvMode::Inherit,
                 aliases: Some(vec!["b".to_string()]),
                 category: Some("development".to_string()),
+                show_output: false,
+                timeout: None,
+                kill_signal: KillSignal::Term,
+                confirm: false,
+                danger_level: DangerLevel::Low,
+                params: Vec::new(),
             },
             Command {
                 name: "test".to_string(),
                 description: "Run tests".to_string(),
                 executable: "cargo".to_string(),
                 args: vec!["test".to_string()],
+                exec: ExecMode::Direct,
                 working_dir: None,
                 env_vars: None,
+                env_mode: EnvMode::Inherit,
                 aliases: Some(vec!["t".to_string()]),
                 category: Some("development".to_string()),
+                show_output: false,
+                timeout: None,
+                kill_signal: KillSignal::Term,
+                confirm: false,
+                danger_level: DangerLevel::Low,
+                params: Vec::new(),
             },
             Command {
                 name: "deploy".to_string(),
                 description: "Deploy to production".to_string(),
                 executable: "bash".to_string(),
                 args: vec!["-c".to_string(), "echo 'Deploying...'".to_string()],
+                exec: ExecMode::Direct,
                 working_dir: Some("/opt/app".to_string()),
                 env_vars: Some({
                     let mut env = HashMap::new();
                     env.insert("ENVIRONMENT".to_string(), "production".to_string());
                     env
                 }),
+                env_mode: EnvMode::Inherit,
                 aliases: Some(vec!["d".to_string(), "prod".to_string()]),
                 category: Some("deployment".to_string()),
+                show_output: false,
+                timeout: Some(600),
+                kill_signal: KillSignal::Term,
+                confirm: true,
+                danger_level: DangerLevel::High,
+                params: Vec::new(),
             },
         ],
     };
 
-    let json = serde_json::to_string_pretty(&sample_config)?;
-    fs::write(path, json)?;
+    let text = ConfigFormat::from_path(&path).serialize(&sample_config)?;
+    fs::write(path, text)?;
     
     Ok(())
 }
@@ -245,7 +1097,7 @@ mod tests {
 
     #[test]
     fn test_load_config_from_file() {
-        let mut temp_file = NamedTempFile::new().unwrap();
+        let temp_file = NamedTempFile::new().unwrap();
         create_sample_config(temp_file.path()).unwrap();
         
         let registry = CommandRegistry::load_from_file(temp_file.path()).unwrap();
@@ -258,7 +1110,7 @@ mod tests {
 
     #[test]
     fn test_completions() {
-        let mut temp_file = NamedTempFile::new().unwrap();
+        let temp_file = NamedTempFile::new().unwrap();
         create_sample_config(temp_file.path()).unwrap();
         
         let registry = CommandRegistry::load_from_file(temp_file.path()).unwrap();
@@ -266,11 +1118,15 @@ mod tests {
         let completions = registry.get_completions("b");
         assert!(completions.contains(&"build".to_string()));
         assert!(completions.contains(&"b".to_string()));
+
+        // Fuzzy, ranked: "dpl" hits "deploy" but nothing else
+        assert_eq!(registry.get_completions("dpl"), vec!["deploy".to_string()]);
+        assert_eq!(registry.get_completions("t")[0], "t");
     }
 
     #[test]
     fn test_categories() {
-        let mut temp_file = NamedTempFile::new().unwrap();
+        let temp_file = NamedTempFile::new().unwrap();
         create_sample_config(temp_file.path()).unwrap();
         
         let registry = CommandRegistry::load_from_file(temp_file.path()).unwrap();
@@ -282,4 +1138,496 @@ mod tests {
         let dev_commands = registry.get_commands_by_category("development");
         assert_eq!(dev_commands.len(), 2);
     }
+
+    #[test]
+    fn test_sample_config_in_each_format() {
+        let dir = tempfile::TempDir::new().unwrap();
+
+        for name in DEFAULT_CONFIG_FILES {
+            let path = dir.path().join(name);
+            create_sample_config(&path).unwrap();
+
+            let registry = CommandRegistry::load_from_file(&path).unwrap();
+            assert_eq!(registry.get_all_commands().len(), 3, "{}", name);
+            let deploy = registry.get_command("prod").unwrap();
+            assert_eq!(deploy.env_vars.as_ref().unwrap()["ENVIRONMENT"], "production");
+        }
+    }
+
+    #[test]
+    fn test_layered_override() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let system = dir.path().join("commands.json");
+        let project = dir.path().join("commands.toml");
+        create_sample_config(&system).unwrap();
+        fs::write(
+            &project,
+            r#"
+                version = "1.0"
+
+                [[commands]]
+                name = "deploy"
+                description = "Deploy to staging"
+                executable = "bash"
+                args = ["deploy.sh", "staging"]
+                aliases = ["d", "b"]
+            "#,
+        )
+        .unwrap();
+
+        let registry = CommandRegistry::load_layered(&[&system, &project]).unwrap();
+        assert_eq!(registry.sources(), &[system.clone(), project.clone()]);
+        assert_eq!(registry.get_all_commands().len(), 3);
+
+        // Same name replaces the earlier command, including its aliases
+        let deploy = registry.get_command("deploy").unwrap();
+        assert_eq!(deploy.description, "Deploy to staging");
+        assert!(registry.get_command("prod").is_none());
+        assert_eq!(registry.source_of("deploy"), Some(project.as_path()));
+
+        // An earlier alias moves to the overriding command
+        assert_eq!(registry.get_command("b").unwrap().name, "deploy");
+        assert_eq!(registry.source_of("build"), Some(system.as_path()));
+        assert!(registry.get_completions("b").contains(&"build".to_string()));
+    }
+
+    #[test]
+    fn test_includes_and_packs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let packs = dir.path().join("packs");
+        fs::create_dir(&packs).unwrap();
+
+        fs::write(
+            dir.path().join("commands.json"),
+            r#"{
+                "version": "1.0",
+                "include": ["packs/*.toml", { "path": "ops.json", "namespace": "k8s" }],
+                "commands": [
+                    { "name": "status", "description": "", "executable": "echo", "args": [] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            packs.join("git.toml"),
+            r#"
+                version = "1.0"
+                namespace = "git"
+
+                [[commands]]
+                name = "status"
+                description = "Show git status"
+                executable = "git"
+                args = ["status"]
+                aliases = ["st"]
+            "#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("ops.json"),
+            r#"{ "version": "1.0", "commands": [
+                { "name": "pods", "description": "", "executable": "kubectl", "args": ["get", "pods"] }
+            ] }"#,
+        )
+        .unwrap();
+
+        let registry = CommandRegistry::load_from_file(dir.path().join("commands.json")).unwrap();
+        assert_eq!(registry.sources().len(), 3);
+        assert_eq!(registry.get_command("git:st").unwrap().executable, "git");
+        assert_eq!(registry.get_command("k8s:pods").unwrap().executable, "kubectl");
+        assert_eq!(registry.get_command("status").unwrap().executable, "echo");
+        assert_eq!(registry.source_of("git:status"), Some(packs.join("git.toml").as_path()));
+        // A pack added later matches the pattern, so its directory is watched
+        assert!(registry.watch_paths().contains(&packs));
+    }
+
+    #[test]
+    fn test_diamond_include_loads_once() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = |name: &str| dir.path().join(name);
+        fs::write(path("a.json"), r#"{ "version": "1.0", "include": ["b.json", "c.json"] }"#).unwrap();
+        fs::write(path("b.json"), r#"{ "version": "1.0", "include": ["d.json"] }"#).unwrap();
+        fs::write(path("c.json"), r#"{ "version": "1.0", "include": ["./d.json"] }"#).unwrap();
+        fs::write(
+            path("d.json"),
+            r#"{ "version": "1.0", "commands": [
+                { "name": "shared", "description": "", "executable": "echo", "args": [] }
+            ] }"#,
+        )
+        .unwrap();
+
+        let registry = CommandRegistry::load_from_file(path("a.json")).unwrap();
+        assert_eq!(registry.sources(), [path("d.json"), path("b.json"), path("c.json"), path("a.json")]);
+        assert!(registry.get_command("shared").is_some());
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let a = dir.path().join("a.json");
+        let b = dir.path().join("b.json");
+        fs::write(&a, r#"{ "version": "1.0", "include": ["b.json"] }"#).unwrap();
+        fs::write(&b, r#"{ "version": "1.0", "include": ["a.json", "missing.json"] }"#).unwrap();
+
+        let Err(ConfigError::Invalid(problems)) = CommandRegistry::load_from_file(&a) else {
+            panic!("expected include problems");
+        };
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].message, "include cycle");
+        assert_eq!(problems[1].message, "included file not found");
+    }
+
+    #[test]
+    fn test_reload_keeps_old_registry_on_error() {
+        let temp_file = NamedTempFile::new().unwrap();
+        create_sample_config(temp_file.path()).unwrap();
+
+        let registry = CommandRegistry::load_from_file(temp_file.path()).unwrap();
+        assert_eq!(registry.sources(), &[temp_file.path().to_path_buf()]);
+
+        fs::write(temp_file.path(), "{ \"version\": \"1.0\", \"commands\": [").unwrap();
+        assert!(registry.reload().is_err());
+        assert_eq!(registry.get_all_commands().len(), 3);
+
+        create_sample_config(temp_file.path()).unwrap();
+        assert_eq!(registry.reload().unwrap().get_all_commands().len(), 3);
+    }
+
+    #[test]
+    fn test_validation_collects_all_problems() {
+        let temp_file = NamedTempFile::new().unwrap();
+        fs::write(
+            temp_file.path(),
+            r#"{
+  "version": "1.0",
+  "commands": [
+    { "name": "deploy", "description": "", "executable": "bash", "args": [],
+      "aliases": ["d"] },
+    { "name": "delete", "description": "", "executable": "", "args": [],
+      "aliases": ["d", "deploy"] }
+  ]
+}"#,
+        )
+        .unwrap();
+
+        let Err(ConfigError::Invalid(problems)) = CommandRegistry::load_from_file(temp_file.path()) else {
+            panic!("expected validation errors");
+        };
+        assert_eq!(problems.len(), 3);
+
+        assert_eq!(problems[0].message, "command executable is empty");
+        assert_eq!(problems[1].message, "duplicate alias `d`");
+        assert_eq!(problems[1].key.as_deref(), Some("commands[1].aliases[0]"));
+        assert_eq!(problems[1].span, Some(Span { line: 7, column: 19, len: 3 }));
+        assert_eq!(
+            problems[1].hint.as_deref(),
+            Some("`d` is already used by command `deploy` at line 5")
+        );
+        assert_eq!(problems[2].message, "duplicate alias `deploy`");
+        assert!(problems[2].render().contains("      \"aliases\": [\"d\", \"deploy\"] }"));
+
+        // A second command with the same name is told apart by position
+        let commands: Vec<Command> = serde_json::from_value(serde_json::json!([
+            { "name": "deploy", "description": "", "executable": "a", "args": [] },
+            { "name": "deploy", "description": "", "executable": "b", "args": [], "aliases": ["deploy"] },
+        ]))
+        .unwrap();
+        let hints: Vec<Option<String>> = validate(&commands, |_| None).into_iter().map(|problem| problem.hint).collect();
+        assert_eq!(
+            hints,
+            [
+                Some("`deploy` is already used by the other command named `deploy`, commands[0]".to_string()),
+                Some("`deploy` is already used by the other command named `deploy`, commands[0]".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validation_checks_params() {
+        let temp_file = NamedTempFile::new().unwrap();
+        fs::write(
+            temp_file.path(),
+            r#"{
+  "version": "1.0",
+  "commands": [
+    { "name": "deploy", "description": "", "executable": "deploy.sh", "args": ["{env}", "${TOKEN"],
+      "params": [
+        { "name": "env", "type": "choice" },
+        { "name": "replicas", "type": "number", "default": "many" },
+        { "name": "query" },
+        { "name": "replicas" }
+      ] },
+    { "name": "find", "description": "", "executable": "grep", "args": ["-r", "'{query}'"], "exec": "sh" }
+  ]
+}"#,
+        )
+        .unwrap();
+
+        let Err(ConfigError::Invalid(problems)) = CommandRegistry::load_from_file(temp_file.path()) else {
+            panic!("expected validation errors");
+        };
+        let messages: Vec<&str> = problems.iter().map(|p| p.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "unclosed `${` in `${TOKEN`",
+                "choice parameter `env` has no choices",
+                "invalid default: `replicas` must be a number",
+                "parameter `query` shadows a built-in placeholder",
+                "duplicate parameter `replicas`",
+                "placeholder `{query}` is inside quotes",
+            ]
+        );
+        assert_eq!(problems[0].key.as_deref(), Some("commands[0].args[1]"));
+        assert_eq!(problems[5].key.as_deref(), Some("commands[1].args[1]"));
+        assert_eq!(problems[1].span.map(|span| span.line), Some(6));
+    }
+
+    #[test]
+    fn test_syntax_error_location() {
+        let temp_file = NamedTempFile::new().unwrap();
+        fs::write(temp_file.path(), "{\n  \"version\": \"1.0\",\n  \"commands\": [,]\n}").unwrap();
+
+        let Err(err) = CommandRegistry::load_from_file(temp_file.path()) else {
+            panic!("expected a syntax error");
+        };
+        let ConfigError::Invalid(ref problems) = err else {
+            panic!("expected a located syntax error");
+        };
+        assert_eq!(problems[0].span.map(|span| span.line), Some(3));
+        assert!(err.to_string().contains(":3:"));
+    }
+
+    #[test]
+    fn test_hotkey_setting() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let user = dir.path().join("commands.toml");
+        let project = dir.path().join("commands.json");
+        fs::write(&user, "version = \"1.0\"\nhotkey = \"ctrl+alt+k\"\n").unwrap();
+        fs::write(&project, r#"{ "version": "1.0", "commands": [] }"#).unwrap();
+
+        assert_eq!(CommandRegistry::load_layered(&[&project]).unwrap().hotkey(), HotkeySpec::default());
+        let registry = CommandRegistry::load_layered(&[&user, &project]).unwrap();
+        assert_eq!(registry.hotkey().to_string(), "Ctrl+Alt+K");
+
+        // Bindings from a later file replace earlier ones for the same keys
+        fs::write(
+            &user,
+            r#"
+                version = "1.0"
+                hotkey = "ctrl+alt+k"
+
+                [[hotkeys]]
+                keys = "Super+G"
+                action = { category = "development" }
+
+                [[hotkeys]]
+                keys = "Ctrl+Shift+V"
+                action = "clipboard"
+
+                [[snippets]]
+                name = "email"
+                text = "ada@example.com"
+            "#,
+        )
+        .unwrap();
+        fs::write(
+            &project,
+            r#"{ "version": "1.0", "commands": [{ "name": "build", "description": "", "executable": "cargo", "args": [] }],
+                 "hotkeys": [{ "keys": "super+g", "action": { "command": "build" } }] }"#,
+        )
+        .unwrap();
+        let registry = CommandRegistry::load_layered(&[&user, &project]).unwrap();
+        let bindings: Vec<String> = registry
+            .hotkey_bindings()
+            .iter()
+            .map(|binding| format!("{} {:?}", binding.keys, binding.action))
+            .collect();
+        assert_eq!(bindings, ["Ctrl+Alt+K Launcher", "Ctrl+Shift+V Clipboard", "Super+G Command(\"build\")"]);
+        assert_eq!(registry.snippets()[0].text, "ada@example.com");
+
+        fs::write(
+            &project,
+            r#"{ "version": "1.0", "hotkeys": [{ "keys": "F13", "action": { "command": "nope" } }] }"#,
+        )
+        .unwrap();
+        let Err(ConfigError::Invalid(problems)) = CommandRegistry::load_layered(&[&user, &project]) else {
+            panic!("expected an unknown command");
+        };
+        assert_eq!(problems[0].message, "hotkey `F13` runs unknown command `nope`");
+
+        fs::write(&project, "{\n  \"version\": \"1.0\",\n  \"hotkey\": \"Ctrl+Spce\"\n}").unwrap();
+        let Err(ConfigError::Invalid(problems)) = CommandRegistry::load_layered(&[&user, &project]) else {
+            panic!("expected an invalid hotkey");
+        };
+        assert_eq!(problems[0].message, "unknown key `Spce` in `Ctrl+Spce`; did you mean `Space`?");
+        assert_eq!(problems[0].span.map(|span| span.line), Some(3));
+    }
+
+    #[test]
+    fn test_sequences() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("commands.toml");
+        let config = |sequences: &str| {
+            format!(
+                r#"
+                    version = "1.0"
+                    sequence_timeout_ms = 600
+
+                    [[hotkeys]]
+                    keys = "F13"
+                    action = {{ command = "status" }}
+
+                    [[commands]]
+                    name = "status"
+                    description = ""
+                    executable = "git"
+                    args = ["status"]
+                    {}
+                "#,
+                sequences
+            )
+        };
+
+        fs::write(
+            &path,
+            config(
+                r#"
+                    [[sequences]]
+                    keys = "Ctrl+Space g s"
+                    action = { command = "status" }
+
+                    [[sequences]]
+                    keys = "Super+Space g"
+                    action = "clipboard"
+                "#,
+            ),
+        )
+        .unwrap();
+        let registry = CommandRegistry::load_from_file(&path).unwrap();
+        assert_eq!(registry.sequences()[0].keys.to_string(), "Ctrl+Space g s");
+        assert_eq!(registry.sequence_timeout(), Duration::from_millis(600));
+        // Leaders without a binding of their own open the launcher
+        let leaders: Vec<String> = registry.hotkey_bindings().iter().map(|binding| binding.keys.to_string()).collect();
+        assert_eq!(leaders, ["Ctrl+Space", "F13", "Super+Space"]);
+
+        fs::write(
+            &path,
+            config(
+                r#"
+                    [[sequences]]
+                    keys = "Ctrl+Space g"
+                    action = { command = "nope" }
+
+                    [[sequences]]
+                    keys = "Ctrl+Space g s"
+                    action = { command = "status" }
+
+                    [[sequences]]
+                    keys = "F13 x"
+                    action = "launcher"
+                "#,
+            ),
+        )
+        .unwrap();
+        let Err(ConfigError::Invalid(problems)) = CommandRegistry::load_from_file(&path) else {
+            panic!("expected invalid sequences");
+        };
+        let messages: Vec<&str> = problems.iter().map(|problem| problem.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "sequence `Ctrl+Space g` runs unknown command `nope`",
+                "sequence `Ctrl+Space g` is the start of `Ctrl+Space g s`",
+                "sequence `F13 x` starts with a hotkey that runs `status`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_engines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let user = dir.path().join("commands.toml");
+        let project = dir.path().join("commands.json");
+        fs::write(
+            dir.path().join("engines.json"),
+            r#"{ "engines": [
+                { "keyword": "g", "name": "Google", "url": "https://www.google.com/search?q={query}", "default": true },
+                { "keyword": "gh", "url": "https://github.com/search?q={query}" }
+            ] }"#,
+        )
+        .unwrap();
+        fs::write(
+            &user,
+            r#"
+                version = "1.0"
+                import_engines = ["engines.json"]
+
+                [[engines]]
+                keyword = "gh"
+                name = "GitHub"
+                url = "https://github.com/search?type=code&q={query}"
+            "#,
+        )
+        .unwrap();
+        fs::write(
+            &project,
+            r#"{ "version": "1.0", "engines": [{ "keyword": "ddg", "url": "https://duckduckgo.com/?q={query}", "default": true }] }"#,
+        )
+        .unwrap();
+
+        // The file's own engines replace imported ones, later files win the default
+        let registry = CommandRegistry::load_layered(&[&user, &project]).unwrap();
+        let keywords: Vec<&str> = registry.engines().iter().map(|engine| engine.label()).collect();
+        assert_eq!(keywords, ["Google", "GitHub", "ddg"]);
+        assert_eq!(registry.default_engine().unwrap().keyword, "ddg");
+        let warnings: Vec<&str> = registry.warnings().iter().map(|warning| warning.message.as_str()).collect();
+        assert_eq!(warnings, ["`g` and `ddg` are both the default search engine; `ddg` is used"]);
+        assert!(registry.watch_paths().contains(&dir.path().join("engines.json")));
+
+        fs::write(
+            &project,
+            "{\n  \"version\": \"1.0\",\n  \"engines\": [{ \"keyword\": \"x\", \"url\": \"https://x.test/\" }]\n}",
+        )
+        .unwrap();
+        let Err(ConfigError::Invalid(problems)) = CommandRegistry::load_layered(&[&user, &project]) else {
+            panic!("expected an engine without a placeholder");
+        };
+        assert_eq!(problems[0].message, "search URL `https://x.test/` has no `{query}` placeholder");
+        assert_eq!(problems[0].span.map(|span| span.line), Some(3));
+
+        fs::write(&user, "version = \"1.0\"\nimport_engines = [\"missing.toml\"]\n").unwrap();
+        fs::write(&project, r#"{ "version": "1.0" }"#).unwrap();
+        let Err(ConfigError::Invalid(problems)) = CommandRegistry::load_layered(&[&user, &project]) else {
+            panic!("expected a missing engine list");
+        };
+        assert_eq!(problems[0].message, "imported engine list not found");
+    }
+
+    #[test]
+    fn test_add_and_remove_commands() {
+        let temp_file = NamedTempFile::new().unwrap();
+        create_sample_config(temp_file.path()).unwrap();
+
+        let mut registry = CommandRegistry::load_from_file(temp_file.path()).unwrap();
+
+        let removed = registry.remove_command("build").unwrap();
+        assert!(registry.get_command("b").is_none());
+        assert!(registry.get_completions("bld").is_empty());
+        // Remaining commands still resolve after the shift
+        assert_eq!(registry.get_command("prod").unwrap().name, "deploy");
+
+        registry.add_command(removed.clone()).unwrap();
+        assert_eq!(registry.get_completions("bld"), vec!["build".to_string()]);
+        assert!(registry.add_command(removed).is_err());
+
+        let results = registry.search("prod");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.name, "deploy");
+        assert!(results[0].1.ranges.is_empty());
+
+        // An index key neither map knows is skipped, not a panic
+        registry.index.insert("production");
+        assert_eq!(registry.search("prod").len(), 1);
+    }
 }
\ No newline at end of file
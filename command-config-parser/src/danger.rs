@@ -0,0 +1,296 @@
+// danger.rs
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How careful the launcher is before running a command.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DangerLevel {
+    /// Runs as soon as it is picked.
+    #[default]
+    Low,
+    /// Asks before running.
+    Medium,
+    /// Asks before running, with cancel preselected.
+    High,
+}
+
+impl DangerLevel {
+    pub fn is_low(&self) -> bool {
+        *self == DangerLevel::Low
+    }
+}
+
+// Prefixes that run the next word as the actual program, with their
+// options that are followed by a value, as in `sudo -u root`. Options are
+// case-sensitive except Windows `/x` switches.
+const WRAPPERS: &[(&str, &[&str])] = &[
+    (
+        "sudo",
+        &[
+            "-u", "-g", "-h", "-p", "-C", "-D", "-r", "-t", "-U", "--user", "--group", "--host", "--prompt",
+            "--close-from", "--chdir", "--role", "--type", "--other-user",
+        ],
+    ),
+    ("doas", &["-u", "-C"]),
+    ("env", &["-u", "-C", "--unset", "--chdir"]),
+    ("nohup", &[]),
+    ("nice", &["-n", "--adjustment"]),
+    ("time", &["-f", "-o", "--format", "--output"]),
+    ("command", &[]),
+    ("exec", &["-a"]),
+    (
+        "xargs",
+        &[
+            "-a", "-d", "-E", "-I", "-L", "-n", "-P", "-s", "--arg-file", "--delimiter", "--max-args", "--max-procs",
+            "--max-chars",
+        ],
+    ),
+    ("call", &[]),
+    ("start", &["/d"]),
+];
+// Shells whose script follows a `-c` flag
+const POSIX_SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh"];
+// Git options before the subcommand that take the next word as a value
+const GIT_VALUES: &[&str] = &["-c", "-C", "--git-dir", "--work-tree", "--namespace"];
+
+/// Why `command_line` looks destructive, e.g. "deletes files recursively
+/// without asking (`rm -rf`)". Empty when nothing risky was found. This
+/// is a guard rail, not a sandbox: it reads words, not shell grammar,
+/// though it does look inside scripts handed to `sh -c`, `cmd /c` and
+/// `pwsh -Command`.
+pub fn risks(command_line: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    let mut add = |risk: &str| {
+        if !found.iter().any(|known| known == risk) {
+            found.push(risk.to_string());
+        }
+    };
+
+    let compact: String = command_line.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.contains(":(){:|:&};:") {
+        add("starts a fork bomb");
+    }
+    if compact.contains(">/dev/sd") || compact.contains(">/dev/nvme") || compact.contains(r">\\.\physicaldrive") {
+        add("overwrites a disk device");
+    }
+
+    for words in commands(command_line) {
+        // Wrapper options are told apart by case, everything after by name
+        let Some(start) = program_start(&words) else {
+            continue;
+        };
+        let words: Vec<String> = words.iter().map(|word| word.to_lowercase()).collect();
+        let program = program_name(&words[start]);
+        let args: Vec<&str> = words[start + 1..].iter().map(String::as_str).collect();
+
+        match program {
+            "rm" | "remove-item" | "ri" => {
+                let recursive = args.iter().any(|a| *a == "--recursive" || *a == "-recurse" || short_flag(a, 'r'));
+                let force = args.iter().any(|a| *a == "--force" || *a == "-force" || short_flag(a, 'f'));
+                if recursive && force {
+                    add("deletes files recursively without asking (`rm -rf`)");
+                }
+            }
+            "del" | "erase" | "rd" | "rmdir" if args.contains(&"/s") => {
+                add("deletes directories recursively (`/s`)");
+            }
+            "shutdown" | "reboot" | "poweroff" | "halt" | "stop-computer" | "restart-computer" => {
+                add("shuts down or restarts the machine");
+            }
+            "init" if matches!(args.first(), Some(&"0") | Some(&"6")) => {
+                add("shuts down or restarts the machine");
+            }
+            "format" | "diskpart" | "format-volume" | "clear-disk" => add("formats or wipes a drive"),
+            program if program.starts_with("mkfs") => add("formats or wipes a drive"),
+            "dd" if args.iter().any(|a| a.starts_with("of=/dev/")) => add("overwrites a disk device"),
+            "git" if force_pushes(&args) => add("force-pushes, rewriting remote history"),
+            _ => {}
+        }
+
+        // A script handed to another shell is checked as a command line
+        if let Some(script) = shell_script(program, &args) {
+            for risk in risks(&script) {
+                add(&risk);
+            }
+        }
+    }
+    found
+}
+
+// Split a command line into commands at `;`, `|`, `&`, newlines, brackets
+// and backticks, and each command into words with their quotes removed.
+// Single quotes keep separators; double quotes do not, since `$(…)` runs
+// inside them. An unclosed quote runs to the end of its command.
+fn commands(line: &str) -> Vec<Vec<String>> {
+    let mut commands = Vec::new();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            // `'\''` is how a quote is written inside single quotes
+            (None, '\\') if chars.peek().is_some_and(|next| matches!(next, '\'' | '"')) => {
+                word.extend(chars.next());
+                in_word = true;
+            }
+            (_, ';' | '|' | '&' | '\n' | '(' | ')' | '`') => {
+                quote = None;
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+                commands.push(std::mem::take(&mut words));
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (_, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    commands.push(words);
+    commands.retain(|words| !words.is_empty());
+    commands
+}
+
+// Where the actual program is, past wrappers such as `sudo -u root`,
+// their options and `NAME=value` assignments
+fn program_start(words: &[String]) -> Option<usize> {
+    // The options of the wrapper seen last that take a value
+    let mut wrapper: Option<&[&str]> = None;
+    let mut i = 0;
+    while let Some(word) = words.get(i) {
+        let name = program_name(word).to_lowercase();
+        if let Some((_, values)) = WRAPPERS.iter().find(|(wrapper, _)| *wrapper == name) {
+            wrapper = Some(values);
+        } else if word.starts_with('-') || (wrapper.is_some() && word.starts_with('/') && word.len() <= 3) {
+            // An option's value goes with it
+            let takes_value = wrapper.is_some_and(|values| {
+                values.iter().any(|value| value == word || (value.starts_with('/') && value.eq_ignore_ascii_case(word)))
+            });
+            if takes_value {
+                i += 1;
+            }
+        } else if !word.is_empty() && !word.contains('=') {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+// `git push --force`, also after options such as `-C repo`. Arguments are
+// lowercase by now, so `-c` stands for both `-c` and `-C`.
+fn force_pushes(args: &[&str]) -> bool {
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        if GIT_VALUES.contains(arg) {
+            i += 2;
+        } else if arg.starts_with('-') {
+            i += 1;
+        } else {
+            return *arg == "push" && args[i + 1..].iter().any(|a| a.starts_with("--force") || *a == "-f");
+        }
+    }
+    false
+}
+
+// The script run by `sh -c '…'`, `cmd /c …` or `pwsh -Command …`
+fn shell_script(program: &str, args: &[&str]) -> Option<String> {
+    if POSIX_SHELLS.contains(&program) {
+        // `-c` may be grouped with other flags, as in `bash -lc`
+        let flag = args.iter().position(|a| short_flag(a, 'c'))?;
+        return args[flag + 1..].iter().find(|a| !a.starts_with('-')).map(|a| a.to_string());
+    }
+    let flag = match program {
+        "cmd" => args.iter().position(|a| *a == "/c" || *a == "/k")?,
+        "pwsh" | "powershell" => args.iter().position(|a| *a == "-c" || (a.len() >= 4 && "-command".starts_with(a)))?,
+        _ => return None,
+    };
+    Some(args[flag + 1..].join(" "))
+}
+
+// `/usr/bin/rm` and `C:\Windows\System32\shutdown.exe` are `rm` and `shutdown`
+fn program_name(word: &str) -> &str {
+    let name = word.rsplit(['/', '\\']).next().unwrap_or(word);
+    name.strip_suffix(".exe").unwrap_or(name)
+}
+
+// `-rf`, `-fr`, `-Rf` and the like; long options do not count
+fn short_flag(arg: &str, flag: char) -> bool {
+    arg.strip_prefix('-')
+        .is_some_and(|flags| !flags.starts_with('-') && flags.chars().all(|c| c.is_ascii_alphabetic()) && flags.contains(flag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_risks() {
+        let risky = [
+            ("rm -rf /tmp/build", "deletes files recursively without asking (`rm -rf`)"),
+            ("cd / && sudo rm -r -f home", "deletes files recursively without asking (`rm -rf`)"),
+            ("Remove-Item -Recurse -Force C:\\data", "deletes files recursively without asking (`rm -rf`)"),
+            ("rd /s /q C:\\temp", "deletes directories recursively (`/s`)"),
+            ("shutdown /s /t 0", "shuts down or restarts the machine"),
+            ("C:\\Windows\\System32\\shutdown.exe -r", "shuts down or restarts the machine"),
+            ("format D: /q", "formats or wipes a drive"),
+            ("sudo mkfs.ext4 /dev/sdb1", "formats or wipes a drive"),
+            ("dd if=img.iso of=/dev/sdb bs=4M", "overwrites a disk device"),
+            ("cat x > /dev/sda", "overwrites a disk device"),
+            ("git push --force origin main", "force-pushes, rewriting remote history"),
+            ("git -C repo push -f", "force-pushes, rewriting remote history"),
+            ("sudo -u root rm -rf /", "deletes files recursively without asking (`rm -rf`)"),
+            ("nice -n 10 env -u HOME rm -rf /tmp", "deletes files recursively without asking (`rm -rf`)"),
+            ("bash -c 'rm -rf x'", "deletes files recursively without asking (`rm -rf`)"),
+            ("sh -ec \"cd /srv; rm -rf cache\"", "deletes files recursively without asking (`rm -rf`)"),
+            ("cmd /d /s /c \"rd /s /q C:\\temp\"", "deletes directories recursively (`/s`)"),
+            ("pwsh -NoProfile -Command Remove-Item -Recurse -Force x", "deletes files recursively without asking (`rm -rf`)"),
+            ("bash -c 'echo ok; rm -rf x'", "deletes files recursively without asking (`rm -rf`)"),
+            ("sudo -i rm -rf /", "deletes files recursively without asking (`rm -rf`)"),
+            ("sudo -s rm -rf ~", "deletes files recursively without asking (`rm -rf`)"),
+            ("env -i rm -rf /", "deletes files recursively without asking (`rm -rf`)"),
+            ("find . | xargs -r rm -rf", "deletes files recursively without asking (`rm -rf`)"),
+            ("time -p nice rm -rf /tmp", "deletes files recursively without asking (`rm -rf`)"),
+            ("xargs -p -n 1 rm -rf", "deletes files recursively without asking (`rm -rf`)"),
+            ("start /D C:\\work rd /s /q temp", "deletes directories recursively (`/s`)"),
+            (":(){ :|:& };:", "starts a fork bomb"),
+        ];
+        for (line, risk) in risky {
+            assert_eq!(risks(line), vec![risk.to_string()], "{}", line);
+        }
+
+        let safe = [
+            "rm build.log",
+            "cargo build --release",
+            "echo format",
+            "git push origin main",
+            "git -C push status",
+            "ls -rf",
+            // The sample deploy command, as `command_line` quotes it
+            r"bash -c 'echo '\''Deploying...'\'''",
+        ];
+        for line in safe {
+            assert!(risks(line).is_empty(), "{}", line);
+        }
+    }
+}
@@ -0,0 +1,352 @@
+// diagnostic.rs
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A 1-based line/column position in a configuration file. `len` is the
+/// width of the offending text in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub len: usize,
+}
+
+/// One problem found in a configuration file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub path: Option<PathBuf>,
+    pub span: Option<Span>,
+    // Location of the offending value, e.g. `commands[2].aliases[0]`
+    pub key: Option<String>,
+    pub message: String,
+    pub hint: Option<String>,
+    source_line: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            path: None,
+            span: None,
+            key: None,
+            message: message.into(),
+            hint: None,
+            source_line: None,
+        }
+    }
+
+    pub fn with_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    // Attach a location, keeping the source line for the snippet
+    pub fn with_span(mut self, span: Span, source: &str) -> Self {
+        self.source_line = source.lines().nth(span.line - 1).map(str::to_string);
+        self.span = Some(span);
+        self
+    }
+
+    /// Pretty, multi-line form with a source snippet:
+    ///
+    /// ```text
+    /// error: duplicate alias `d`
+    ///   --> commands.json:42:18
+    ///    |
+    /// 42 |     "aliases": ["d"],
+    ///    |                 ^^^
+    ///    = help: alias `d` is already used by command `deploy` at line 30
+    /// ```
+    pub fn render(&self) -> String {
+        let mut out = format!("error: {}\n", self.message);
+
+        let line_no = self.span.map(|span| span.line.to_string()).unwrap_or_default();
+        let pad = " ".repeat(line_no.len());
+
+        if let Some(location) = self.location() {
+            out.push_str(&format!("{}--> {}\n", pad, location));
+        }
+
+        if let (Some(span), Some(ref line)) = (self.span, &self.source_line) {
+            let caret_pad = " ".repeat(span.column.saturating_sub(1));
+            let carets = "^".repeat(span.len.max(1));
+            out.push_str(&format!("{} |\n", pad));
+            out.push_str(&format!("{} | {}\n", line_no, line));
+            out.push_str(&format!("{} | {}{}\n", pad, caret_pad, carets));
+        }
+
+        if let Some(ref hint) = self.hint {
+            out.push_str(&format!("{} = help: {}\n", pad, hint));
+        }
+
+        out
+    }
+
+    fn location(&self) -> Option<String> {
+        let path = self.path.as_ref().map(|path| path.display().to_string());
+        match (path, self.span) {
+            (Some(path), Some(span)) => Some(format!("{}:{}:{}", path, span.line, span.column)),
+            (Some(path), None) => Some(path),
+            (None, Some(span)) => Some(format!("line {}, column {}", span.line, span.column)),
+            (None, None) => None,
+        }
+    }
+}
+
+// Short single-line form, e.g. for a status line or log
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = self.location() {
+            write!(f, "{}: ", location)?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(ref hint) = self.hint {
+            write!(f, " ({})", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where each value of a JSON document starts, keyed by its path
+/// (`version`, `commands[0].name`, `commands[0].aliases[1]`, ...).
+/// serde does not keep positions, so this scans the text separately.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    spans: HashMap<String, Span>,
+}
+
+impl SourceMap {
+    pub fn from_json(source: &str) -> Self {
+        let mut scanner = Scanner {
+            chars: source.chars().collect(),
+            pos: 0,
+            line: 1,
+            column: 1,
+            map: SourceMap::default(),
+        };
+        scanner.value(String::new());
+        scanner.map
+    }
+
+    pub fn get(&self, path: &str) -> Option<Span> {
+        self.spans.get(path).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+}
+
+struct Scanner {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+    map: SourceMap,
+}
+
+impl Scanner {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                // Comments, so JSONC files map too
+                Some('/') if self.chars.get(self.pos + 1) == Some(&'/') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                Some('/') if self.chars.get(self.pos + 1) == Some(&'*') => {
+                    self.bump();
+                    self.bump();
+                    while self.peek().is_some()
+                        && !(self.peek() == Some('*') && self.chars.get(self.pos + 1) == Some(&'/'))
+                    {
+                        self.bump();
+                    }
+                    self.bump();
+                    self.bump();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    // Returns false on malformed input; serde reports the real error
+    fn value(&mut self, path: String) -> bool {
+        self.skip_whitespace();
+        let (line, column, start) = (self.line, self.column, self.pos);
+
+        let ok = match self.peek() {
+            Some('{') => self.object(&path),
+            Some('[') => self.array(&path),
+            Some('"') => self.string().is_some(),
+            Some(_) => {
+                while matches!(self.peek(), Some(c) if !c.is_whitespace() && !",]}".contains(c)) {
+                    self.bump();
+                }
+                self.pos > start
+            }
+            None => false,
+        };
+
+        let len = if self.line == line { self.pos - start } else { 1 };
+        self.map.spans.insert(path, Span { line, column, len });
+        ok
+    }
+
+    fn object(&mut self, path: &str) -> bool {
+        self.bump();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    return true;
+                }
+                Some(',') => {
+                    self.bump();
+                }
+                Some('"') => {
+                    let Some(key) = self.string() else {
+                        return false;
+                    };
+                    self.skip_whitespace();
+                    if self.bump() != Some(':') {
+                        return false;
+                    }
+                    let child = if path.is_empty() {
+                        key
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+                    if !self.value(child) {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+    }
+
+    fn array(&mut self, path: &str) -> bool {
+        self.bump();
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(']') => {
+                    self.bump();
+                    return true;
+                }
+                Some(',') => {
+                    self.bump();
+                }
+                Some(_) => {
+                    if !self.value(format!("{}[{}]", path, index)) {
+                        return false;
+                    }
+                    index += 1;
+                }
+                None => return false,
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.bump();
+        let mut text = String::new();
+        loop {
+            match self.bump()? {
+                '"' => return Some(text),
+                '\\' => {
+                    let escaped = self.bump()?;
+                    text.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        other => other,
+                    });
+                }
+                c => text.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"{
+  "version": "1.0",
+  "commands": [
+    {
+      "name": "build",
+      "aliases": ["b", "d"]
+    }
+  ]
+}"#;
+
+    #[test]
+    fn test_source_map() {
+        let map = SourceMap::from_json(SOURCE);
+
+        assert_eq!(map.get("version"), Some(Span { line: 2, column: 14, len: 5 }));
+        assert_eq!(map.get("commands[0].name"), Some(Span { line: 5, column: 15, len: 7 }));
+        assert_eq!(map.get("commands[0].aliases[1]"), Some(Span { line: 6, column: 24, len: 3 }));
+        assert_eq!(map.get("commands[1]"), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let map = SourceMap::from_json(SOURCE);
+        let diagnostic = Diagnostic::new("duplicate alias `d`")
+            .with_path("commands.json")
+            .with_key("commands[0].aliases[1]")
+            .with_span(map.get("commands[0].aliases[1]").unwrap(), SOURCE)
+            .with_hint("alias `d` is already used by command `deploy` at line 12");
+
+        assert_eq!(
+            diagnostic.render(),
+            "error: duplicate alias `d`\n \
+             --> commands.json:6:24\n  \
+             |\n\
+             6 |       \"aliases\": [\"b\", \"d\"]\n  \
+             |                        ^^^\n  \
+             = help: alias `d` is already used by command `deploy` at line 12\n"
+        );
+        assert_eq!(
+            diagnostic.to_string(),
+            "commands.json:6:24: duplicate alias `d` (alias `d` is already used by command `deploy` at line 12)"
+        );
+    }
+}
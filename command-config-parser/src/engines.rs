@@ -0,0 +1,171 @@
+// engines.rs
+use crate::config::ConfigError;
+use crate::diagnostic::{Diagnostic, Span};
+use crate::format::ConfigFormat;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+// Replaced by the URL-encoded search terms
+pub const QUERY_PLACEHOLDER: &str = "{query}";
+
+/// A web search run by typing its keyword before the search terms, e.g.
+/// `gh totalcontrol`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SearchEngine {
+    /// Typed before the search terms, e.g. `gh`.
+    #[schemars(pattern(crate::schema::ALIAS))]
+    pub keyword: String,
+    /// Shown in the launcher; defaults to the keyword.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Page to open, with `{query}` where the search terms go, e.g.
+    /// `https://github.com/search?q={query}`.
+    pub url: String,
+    /// Search here when nothing else matches the query.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub default: bool,
+}
+
+impl SearchEngine {
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.keyword)
+    }
+}
+
+// Checks the schema cannot express: every URL takes the query, keywords
+// are unique and only one engine is the default. `key` names the list,
+// e.g. `engines`.
+pub fn validate(engines: &[SearchEngine], key: &str, locate: impl Fn(&str) -> Option<Span>) -> Vec<Diagnostic> {
+    let mut problems = Vec::new();
+    let mut default: Option<&str> = None;
+    for (i, engine) in engines.iter().enumerate() {
+        if !engine.url.contains(QUERY_PLACEHOLDER) {
+            problems.push(
+                Diagnostic::new(format!("search URL `{}` has no `{}` placeholder", engine.url, QUERY_PLACEHOLDER))
+                    .with_key(format!("{}[{}].url", key, i))
+                    .with_hint("e.g. https://github.com/search?q={query}"),
+            );
+        }
+        if let Some(first) = engines[..i].iter().position(|other| other.keyword == engine.keyword) {
+            let at_line = locate(&format!("{}[{}].keyword", key, first))
+                .map(|span| format!(" at line {}", span.line))
+                .unwrap_or_default();
+            problems.push(
+                Diagnostic::new(format!("search keyword `{}` is used twice", engine.keyword))
+                    .with_key(format!("{}[{}].keyword", key, i))
+                    .with_hint(format!("first used by engine #{}{}", first + 1, at_line)),
+            );
+        }
+        if engine.default {
+            match default {
+                Some(first) => problems.push(
+                    Diagnostic::new(format!("`{}` and `{}` are both the default search engine", first, engine.keyword))
+                        .with_key(format!("{}[{}].default", key, i)),
+                ),
+                None => default = Some(&engine.keyword),
+            }
+        }
+    }
+    problems
+}
+
+/// Read a list of search engines from a TOML or JSON file: a JSON array
+/// of engines, or an `engines` table array in either format.
+pub fn load_engines<P: AsRef<Path>>(path: P) -> Result<Vec<SearchEngine>, ConfigError> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(ConfigError::FileNotFound);
+    }
+
+    let content = fs::read_to_string(path)?;
+    let format = ConfigFormat::from_path(path);
+    let mut document = format.parse_value(&content).map_err(|err| err.with_path(path))?;
+    let (key, list) = match document {
+        Value::Array(_) => ("", document),
+        _ => ("engines", document.get_mut("engines").map(Value::take).unwrap_or(Value::Array(Vec::new()))),
+    };
+
+    let engines: Vec<SearchEngine> = serde_json::from_value(list).map_err(|err| {
+        ConfigError::Invalid(vec![Diagnostic::new(format!("invalid search engine list: {}", err)).with_path(path)])
+    })?;
+
+    // Imported lists are not checked against the schema, so keywords are
+    // checked here
+    let mut problems: Vec<Diagnostic> = engines
+        .iter()
+        .enumerate()
+        .filter(|(_, engine)| engine.keyword.is_empty() || engine.keyword.contains(char::is_whitespace))
+        .map(|(i, engine)| {
+            Diagnostic::new(format!("search keyword `{}` must be a single word", engine.keyword))
+                .with_key(format!("{}[{}].keyword", key, i))
+        })
+        .collect();
+    let map = format.source_map(&content);
+    problems.extend(validate(&engines, key, |key| map.get(key)));
+    let problems: Vec<Diagnostic> = problems
+        .into_iter()
+        .map(|problem| match problem.key.as_deref().and_then(|key| map.get(key)) {
+            Some(span) => problem.with_span(span, &content),
+            None => problem,
+        })
+        .collect();
+    if !problems.is_empty() {
+        return Err(ConfigError::Invalid(problems).with_path(path));
+    }
+    Ok(engines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_engines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let toml = dir.path().join("engines.toml");
+        fs::write(
+            &toml,
+            r#"
+                [[engines]]
+                keyword = "gh"
+                name = "GitHub"
+                url = "https://github.com/search?q={query}"
+
+                [[engines]]
+                keyword = "ddg"
+                url = "https://duckduckgo.com/?q={query}"
+                default = true
+            "#,
+        )
+        .unwrap();
+        let engines = load_engines(&toml).unwrap();
+        assert_eq!(engines[0].label(), "GitHub");
+        assert_eq!(engines[1].label(), "ddg");
+        assert!(engines[1].default);
+
+        let json = dir.path().join("engines.json");
+        fs::write(
+            &json,
+            "[\n  { \"keyword\": \"g\", \"url\": \"https://www.google.com/search?q={query}\", \"default\": true },\n  \
+             { \"keyword\": \"g\", \"url\": \"https://example.com/\", \"default\": true }\n]",
+        )
+        .unwrap();
+        let Err(ConfigError::Invalid(problems)) = load_engines(&json) else {
+            panic!("expected invalid engines");
+        };
+        let messages: Vec<&str> = problems.iter().map(|problem| problem.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "search URL `https://example.com/` has no `{query}` placeholder",
+                "search keyword `g` is used twice",
+                "`g` and `g` are both the default search engine",
+            ]
+        );
+        assert_eq!(problems[0].span.map(|span| span.line), Some(3));
+        assert_eq!(problems[1].hint.as_deref(), Some("first used by engine #1 at line 2"));
+    }
+}
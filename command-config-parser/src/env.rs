@@ -0,0 +1,191 @@
+// env.rs
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How a command's `env_vars` combine with the launcher's environment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvMode {
+    /// The launcher's environment, with `env_vars` set on top.
+    #[default]
+    Inherit,
+    /// Like `inherit`, but each of `env_vars` is put in front of the
+    /// existing value as a path list entry, e.g. to extend `PATH`.
+    Extend,
+    /// Only `env_vars`, plus the few system variables programs need to
+    /// start, such as `PATH` and, on Windows, `SystemRoot`.
+    Replace,
+}
+
+// Kept under `EnvMode::Replace`; many programs fail without them
+#[cfg(windows)]
+const SYSTEM_VARS: &[&str] = &["PATH", "PATHEXT", "SystemRoot", "SystemDrive", "windir", "ComSpec", "TEMP", "TMP"];
+#[cfg(not(windows))]
+const SYSTEM_VARS: &[&str] = &["PATH", "HOME"];
+
+impl EnvMode {
+    pub fn is_inherit(&self) -> bool {
+        *self == EnvMode::Inherit
+    }
+}
+
+/// Expand `~`, `$VAR`, `${VAR}`, `${VAR:-default}` and `%VAR%` in `text`.
+///
+/// An unset `$VAR` is an error unless it has a default; `$$` writes a
+/// literal `$`. `%VAR%` is only replaced when the variable is set, as in
+/// `cmd.exe`, so text like `date +%d%m` passes through.
+pub fn expand_vars(text: &str, lookup: &impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    if rest == "~" || rest.starts_with("~/") || rest.starts_with("~\\") {
+        let home = lookup("HOME")
+            .or_else(|| lookup("USERPROFILE"))
+            .ok_or("cannot expand `~`: neither HOME nor USERPROFILE is set")?;
+        out.push_str(&home);
+        rest = &rest[1..];
+    }
+
+    while let Some(i) = rest.find(['$', '%']) {
+        out.push_str(&rest[..i]);
+        let sigil = &rest[i..i + 1];
+        let after = &rest[i + 1..];
+
+        if sigil == "%" {
+            let value = after
+                .find('%')
+                .filter(|&end| is_name(&after[..end]))
+                .and_then(|end| lookup(&after[..end]).map(|value| (value, end)));
+            match value {
+                Some((value, end)) => {
+                    out.push_str(&value);
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    out.push('%');
+                    rest = after;
+                }
+            }
+            continue;
+        }
+
+        if let Some(after) = after.strip_prefix('$') {
+            out.push('$');
+            rest = after;
+        } else if let Some(braced) = after.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| format!("unclosed `${{` in `{}`", text))?;
+            let inner = &braced[..end];
+            let (name, default) = match inner.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (inner, None),
+            };
+            if !is_name(name) {
+                return Err(format!("invalid variable `${{{}}}`", inner));
+            }
+
+            let value = match (lookup(name).filter(|value| !value.is_empty()), default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => expand_vars(default, lookup)?,
+                (None, None) => return Err(unset(name)),
+            };
+            out.push_str(&value);
+            rest = &braced[end + 1..];
+        } else {
+            let len = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            let name = &after[..len];
+            if is_name(name) {
+                out.push_str(&lookup(name).ok_or_else(|| unset(name))?);
+                rest = &after[len..];
+            } else {
+                // `$1`, a trailing `$` and the like are not variables
+                out.push('$');
+                rest = after;
+            }
+        }
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// The variables a command started with [`EnvMode::Replace`] still gets
+/// from the launcher, before its own `env_vars`.
+pub fn system_vars(lookup: &impl Fn(&str) -> Option<String>) -> Vec<(String, String)> {
+    SYSTEM_VARS
+        .iter()
+        .filter_map(|name| Some((name.to_string(), lookup(name)?)))
+        .collect()
+}
+
+/// Syntax errors in `text` that [`expand_vars`] would report whatever the
+/// environment holds.
+pub fn check_vars(text: &str) -> Result<(), String> {
+    // Every variable set, so only malformed references fail
+    expand_vars(text, &|_| Some(String::from("x"))).map(drop)
+}
+
+fn is_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn unset(name: &str) -> String {
+    format!(
+        "environment variable `{}` is not set (use `${{{}:-default}}` to make it optional)",
+        name, name
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/ada".to_string()),
+            "APP" => Some("web".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand_vars() {
+        let cases = [
+            ("~/bin", "/home/ada/bin"),
+            ("a~b", "a~b"),
+            ("$APP-$APP", "web-web"),
+            ("${APP}s", "webs"),
+            ("${MISSING:-dev}/${EMPTY:-x}", "dev/x"),
+            ("${MISSING:-$APP}", "web"),
+            ("%APP%.exe", "web.exe"),
+            ("date +%d%m %MISSING%", "date +%d%m %MISSING%"),
+            ("awk '{print $1}' costs $$5 $", "awk '{print $1}' costs $5 $"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(expand_vars(text, &lookup).as_deref(), Ok(expected), "{}", text);
+        }
+        // Replace mode keeps only the system variables that are set
+        if cfg!(not(windows)) {
+            assert_eq!(system_vars(&lookup), [("HOME".to_string(), "/home/ada".to_string())]);
+        }
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        let err = expand_vars("--token=$TOKEN", &lookup).unwrap_err();
+        assert_eq!(
+            err,
+            "environment variable `TOKEN` is not set (use `${TOKEN:-default}` to make it optional)"
+        );
+        assert!(expand_vars("~", &|_| None).is_err());
+
+        assert!(check_vars("$ANYTHING ${ANY:-x}").is_ok());
+        assert_eq!(check_vars("${APP").unwrap_err(), "unclosed `${` in `${APP`");
+        assert_eq!(check_vars("${A B}").unwrap_err(), "invalid variable `${A B}`");
+    }
+}
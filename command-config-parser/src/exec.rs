@@ -0,0 +1,322 @@
+// exec.rs
+use crate::config::Command;
+use crate::params::{segments, Placeholder, Segment};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How a command is started. In a shell mode `executable` and `args`,
+/// joined by spaces, are a script for that shell, and values substituted
+/// into them are quoted for it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecMode {
+    /// Start `executable` directly with `args` as its arguments; no shell
+    /// sees them.
+    #[default]
+    Direct,
+    /// `sh -c`
+    Sh,
+    /// `bash -c`
+    Bash,
+    /// PowerShell 7, `pwsh -Command`
+    Pwsh,
+    /// `cmd /C` on Windows
+    Cmd,
+}
+
+impl ExecMode {
+    pub fn is_direct(&self) -> bool {
+        *self == ExecMode::Direct
+    }
+
+    // The shell typed-in command lines run under
+    pub fn platform_shell() -> Self {
+        if cfg!(windows) {
+            ExecMode::Cmd
+        } else {
+            ExecMode::Sh
+        }
+    }
+
+    /// The shell program and the arguments that go before the script.
+    pub fn shell(&self) -> Option<(&'static str, &'static [&'static str])> {
+        match self {
+            ExecMode::Direct => None,
+            ExecMode::Sh => Some(("sh", &["-c"])),
+            ExecMode::Bash => Some(("bash", &["-c"])),
+            ExecMode::Pwsh => Some(("pwsh", &["-NoProfile", "-NonInteractive", "-Command"])),
+            // No AutoRun, no `!VAR!` expansion, and the outer quotes stripped
+            ExecMode::Cmd => Some(("cmd", &["/D", "/V:OFF", "/S", "/C"])),
+        }
+    }
+
+    pub fn script(&self, executable: &str, args: &[String]) -> String {
+        std::iter::once(executable)
+            .chain(args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Placeholders in a script that sit inside quotes written around
+    /// them, as in `'{query}'`. Values are quoted as they are put in, and
+    /// quoting them again breaks the quoting.
+    pub fn quoted_placeholders(&self, script: &str) -> Vec<Placeholder> {
+        let mut found = Vec::new();
+        if self.is_direct() {
+            return found;
+        }
+        let mut quote = None;
+        let mut escaped = false;
+        for segment in segments(script) {
+            let text = match segment {
+                Segment::Placeholder(placeholder) => {
+                    if quote.is_some() {
+                        found.push(placeholder);
+                    }
+                    escaped = false;
+                    continue;
+                }
+                Segment::Text(text) => text,
+            };
+            for c in text.chars() {
+                if escaped {
+                    escaped = false;
+                    continue;
+                }
+                match (quote, c) {
+                    (Some(q), c) if c == q => quote = None,
+                    (Some('\''), _) => {}
+                    (_, '\\') if matches!(self, ExecMode::Sh | ExecMode::Bash) => escaped = true,
+                    (_, '`') if *self == ExecMode::Pwsh => escaped = true,
+                    (None, '\'') if *self != ExecMode::Cmd => quote = Some(c),
+                    (None, '"') => quote = Some(c),
+                    _ => {}
+                }
+            }
+        }
+        found
+    }
+
+    /// `value` written so the shell passes it on as one literal word.
+    /// Direct execution needs no quoting.
+    pub fn quote(&self, value: &str) -> Result<String, String> {
+        if value.contains('\0') {
+            return Err("values cannot contain NUL bytes".to_string());
+        }
+        match self {
+            ExecMode::Direct => Ok(value.to_string()),
+            ExecMode::Sh | ExecMode::Bash => Ok(quote_posix(value)),
+            ExecMode::Pwsh => Ok(quote_pwsh(value)),
+            ExecMode::Cmd => quote_cmd(value),
+        }
+    }
+}
+
+impl Command {
+    /// The command as one line, for showing before it runs: the script in
+    /// a shell mode, otherwise the arguments quoted for the local shell.
+    pub fn command_line(&self) -> String {
+        if !self.exec.is_direct() {
+            return self.exec.script(&self.executable, &self.args);
+        }
+        let shell = ExecMode::platform_shell();
+        let words: Vec<String> = std::iter::once(&self.executable)
+            .chain(&self.args)
+            .map(|word| shell.quote(word).unwrap_or_else(|_| word.escape_debug().to_string()))
+            .collect();
+        words.join(" ")
+    }
+}
+
+fn is_plain(value: &str, extra: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || extra.contains(c))
+}
+
+// Single quotes keep everything literal; a quote is closed, escaped and
+// reopened
+fn quote_posix(value: &str) -> String {
+    if is_plain(value, "_-.,/:=+@%") {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+// PowerShell also reads typographic single quotes as quote characters
+fn quote_pwsh(value: &str) -> String {
+    if is_plain(value, "_-./") && !value.starts_with('-') {
+        return value.to_string();
+    }
+    let mut quoted = String::from("'");
+    for c in value.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            quoted.push(c);
+        }
+        quoted.push(c);
+    }
+    quoted.push('\'');
+    quoted
+}
+
+// Double quotes stop cmd from reading `&|<>^`; `%` still expands inside
+// them, so it is split up with an empty `%cd:~,%` substring. Backslashes
+// before a quote are doubled for the program's own argument parsing.
+fn quote_cmd(value: &str) -> Result<String, String> {
+    if value.contains(['\n', '\r']) {
+        return Err("values with line breaks cannot be passed to cmd".to_string());
+    }
+    if is_plain(value, "_-./\\:") {
+        return Ok(value.to_string());
+    }
+
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in value.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2));
+                quoted.push_str("\"\"");
+                backslashes = 0;
+            }
+            c => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                backslashes = 0;
+                if c == '%' {
+                    quoted.push_str("%%cd:~,%");
+                } else {
+                    quoted.push(c);
+                }
+            }
+        }
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    Ok(quoted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        let cases = [
+            (ExecMode::Sh, "plain-1.0", "plain-1.0"),
+            (ExecMode::Sh, "it's", r"'it'\''s'"),
+            (ExecMode::Bash, "$(id)", "'$(id)'"),
+            (ExecMode::Sh, "", "''"),
+            (ExecMode::Pwsh, "it's ‘x’", "'it''s ‘‘x’’'"),
+            (ExecMode::Pwsh, "-Force", "'-Force'"),
+            (ExecMode::Pwsh, "$env:PATH", "'$env:PATH'"),
+            (ExecMode::Cmd, r"C:\Program Files\", r#""C:\Program Files\\""#),
+            (ExecMode::Cmd, "a & b", "\"a & b\""),
+            (ExecMode::Cmd, r#"say "hi""#, r#""say ""hi""""#),
+            (ExecMode::Cmd, "%PATH%", "\"%%cd:~,%PATH%%cd:~,%\""),
+            (ExecMode::Direct, "a & b", "a & b"),
+        ];
+        for (mode, value, expected) in cases {
+            assert_eq!(mode.quote(value).as_deref(), Ok(expected), "{:?} {}", mode, value);
+        }
+
+        assert!(ExecMode::Cmd.quote("a\r\nb").is_err());
+        assert!(ExecMode::Sh.quote("a\0b").is_err());
+
+        let names = |mode: ExecMode, script: &str| -> Vec<String> {
+            mode.quoted_placeholders(script).into_iter().map(|p| p.name).collect()
+        };
+        assert_eq!(names(ExecMode::Sh, "grep '{query}' \"in {dir}\" {file}"), ["query", "dir"]);
+        assert!(names(ExecMode::Sh, r#"echo "a\"" {query} 'b' \' {x}"#).is_empty());
+        assert!(names(ExecMode::Cmd, r#""C:\dir\" {query} it's {x}"#).is_empty());
+        assert_eq!(names(ExecMode::Pwsh, "Write-Host 'it''s {query}'"), ["query"]);
+    }
+
+    const HOSTILE: &[&str] = &[
+        "'; touch pwned; echo '",
+        "$(id)",
+        "`id`",
+        "a\"b",
+        "it's",
+        "*",
+        "~",
+        "$HOME",
+        "a && b | c > d",
+        "",
+        "  spaced  out ",
+        "-n",
+        "line\nbreak",
+        "back\\slash\\",
+        "50% off",
+        "héllo ’quoted’",
+    ];
+
+    // Run a script the way the executor does, from the temp directory so a
+    // successful injection leaves `pwned` there. None when the shell is not
+    // installed.
+    fn run(mode: ExecMode, script: &str) -> Option<String> {
+        let (program, flags) = mode.shell().unwrap();
+        let dir = std::env::temp_dir();
+        let mut cmd = std::process::Command::new(program);
+        cmd.args(flags).current_dir(&dir);
+        #[cfg(windows)]
+        if mode == ExecMode::Cmd {
+            std::os::windows::process::CommandExt::raw_arg(&mut cmd, format!("\"{}\"", script));
+        } else {
+            cmd.arg(script);
+        }
+        #[cfg(not(windows))]
+        cmd.arg(script);
+
+        let output = cmd.output().ok()?;
+        assert!(!dir.join("pwned").exists(), "{:?}: {}", mode, script);
+        Some(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    // Every hostile value must come back out of a real shell unchanged
+    #[cfg(unix)]
+    #[test]
+    fn test_hostile_values_round_trip() {
+        for mode in [ExecMode::Sh, ExecMode::Bash] {
+            for value in HOSTILE {
+                let script = mode.script("printf", &["%s".to_string(), mode.quote(value).unwrap()]);
+                assert_eq!(run(mode, &script).unwrap(), *value, "{:?}: {}", mode, script);
+            }
+        }
+    }
+
+    // Skipped where PowerShell 7 is not installed
+    #[test]
+    fn test_hostile_values_round_trip_pwsh() {
+        let mode = ExecMode::Pwsh;
+        for value in HOSTILE {
+            let script = mode.script(
+                "[Console]::OutputEncoding = [Text.Encoding]::UTF8; Write-Host -NoNewline",
+                &[mode.quote(value).unwrap()],
+            );
+            let Some(output) = run(mode, &script) else {
+                return;
+            };
+            assert_eq!(output, *value, "{}", script);
+        }
+    }
+
+    // cmd has no command to print an argument as it arrives, so Windows
+    // PowerShell prints it from a script
+    #[cfg(windows)]
+    #[test]
+    fn test_hostile_values_round_trip_cmd() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let echo = dir.path().join("echo.ps1");
+        std::fs::write(&echo, "[Console]::OutputEncoding = [Text.Encoding]::UTF8; [Console]::Out.Write($args[0])").unwrap();
+        let program = format!(
+            "powershell -NoProfile -NonInteractive -ExecutionPolicy Bypass -File {}",
+            ExecMode::Cmd.quote(&echo.to_string_lossy()).unwrap()
+        );
+
+        let mode = ExecMode::Cmd;
+        // Line breaks are refused rather than quoted
+        for value in HOSTILE.iter().filter(|value| !value.contains('\n')) {
+            let script = mode.script(&program, &[mode.quote(value).unwrap()]);
+            assert_eq!(run(mode, &script).unwrap(), *value, "{}", script);
+        }
+    }
+}
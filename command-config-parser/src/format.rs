@@ -0,0 +1,234 @@
+// format.rs
+use crate::config::{validate, CommandConfig, ConfigError};
+use crate::diagnostic::{Diagnostic, SourceMap, Span};
+use crate::engines;
+use crate::hotkey;
+use crate::migrate::migrate;
+use crate::schema;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::path::Path;
+
+/// Configuration file formats, detected from the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    // JSON5, which also covers JSONC (comments and trailing commas)
+    Json5,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    // Unknown or missing extensions are read as plain JSON
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+
+        match extension.as_deref() {
+            Some("jsonc") | Some("json5") => ConfigFormat::Json5,
+            Some("toml") => ConfigFormat::Toml,
+            Some("yaml") | Some("yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    /// Parse a configuration file, upgrading older schema versions first.
+    /// Every schema violation and conflict between commands is reported at
+    /// once, located in the source where possible.
+    pub fn parse(&self, source: &str) -> Result<CommandConfig, ConfigError> {
+        let map = self.source_map(source);
+        let mut document = self.parse_value(source)?;
+        let migrated = migrate(&mut document).map_err(|err| match err {
+            ConfigError::Invalid(problems) => ConfigError::Invalid(locate_all(problems, &map, source)),
+            other => other,
+        })?;
+        // Positions no longer line up once a migration rewrote the document
+        let map = if migrated.is_some() { SourceMap::default() } else { map };
+
+        let mut problems = schema::check(&document);
+        problems.extend(hotkey::check(&document));
+        let parsed = match migrated {
+            Some(version) => serde_json::from_value(document).map_err(|err| {
+                let diagnostic = Diagnostic::new(err.to_string())
+                    .with_hint(format!("while upgrading from version {}", version));
+                ConfigError::Invalid(vec![diagnostic])
+            }),
+            None => self.deserialize(source),
+        };
+
+        let config: CommandConfig = match parsed {
+            Ok(config) => config,
+            // The schema problems explain why the types did not fit, but
+            // only the parser's own error can point into TOML and YAML
+            Err(_) if !problems.is_empty() && !map.is_empty() => {
+                return Err(ConfigError::Invalid(locate_all(problems, &map, source)));
+            }
+            Err(err) => return Err(err),
+        };
+
+        problems.extend(validate(&config.commands, |key| map.get(key)));
+        problems.extend(engines::validate(&config.engines, "engines", |key| map.get(key)));
+        if !problems.is_empty() {
+            return Err(ConfigError::Invalid(locate_all(problems, &map, source)));
+        }
+        Ok(config)
+    }
+
+    // The document as a plain value, before migration
+    pub fn parse_value(&self, source: &str) -> Result<Value, ConfigError> {
+        self.deserialize(source)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, source: &str) -> Result<T, ConfigError> {
+        let parsed = match self {
+            ConfigFormat::Json => serde_json::from_str(source).map_err(|err| {
+                located(strip_location(&err.to_string()), source, err.line(), err.column())
+            }),
+            ConfigFormat::Json5 => json5::from_str(source).map_err(|err| match err {
+                json5::Error::Message { msg, location } => {
+                    let (line, column) = location.map_or((0, 0), |l| (l.line, l.column));
+                    located(msg, source, line, column)
+                }
+            }),
+            ConfigFormat::Toml => toml::from_str(source).map_err(|err| {
+                let (line, column) = err
+                    .span()
+                    .map_or((0, 0), |span| line_column(source, span.start));
+                located(err.message().to_string(), source, line, column)
+            }),
+            ConfigFormat::Yaml => serde_yaml::from_str(source).map_err(|err| {
+                let (line, column) = err.location().map_or((0, 0), |l| (l.line(), l.column()));
+                located(strip_location(&err.to_string()), source, line, column)
+            }),
+        };
+        parsed.map_err(|diagnostic| ConfigError::Invalid(vec![diagnostic]))
+    }
+
+    pub fn serialize(&self, config: &CommandConfig) -> Result<String, ConfigError> {
+        let text = match self {
+            ConfigFormat::Json | ConfigFormat::Json5 => serde_json::to_string_pretty(config)?,
+            ConfigFormat::Toml => toml::to_string_pretty(config)
+                .map_err(|err| ConfigError::SerializeError(err.to_string()))?,
+            ConfigFormat::Yaml => serde_yaml::to_string(config)
+                .map_err(|err| ConfigError::SerializeError(err.to_string()))?,
+        };
+        Ok(text)
+    }
+
+    // Value positions for diagnostics; only the JSON family can be mapped
+    pub fn source_map(&self, source: &str) -> SourceMap {
+        match self {
+            ConfigFormat::Json | ConfigFormat::Json5 => SourceMap::from_json(source),
+            ConfigFormat::Toml | ConfigFormat::Yaml => SourceMap::default(),
+        }
+    }
+}
+
+fn locate_all(problems: Vec<Diagnostic>, map: &SourceMap, source: &str) -> Vec<Diagnostic> {
+    problems
+        .into_iter()
+        .map(|diagnostic| match diagnostic.key.as_deref().and_then(|key| map.get(key)) {
+            Some(span) => diagnostic.with_span(span, source),
+            None => diagnostic,
+        })
+        .collect()
+}
+
+fn located(message: String, source: &str, line: usize, column: usize) -> Diagnostic {
+    let diagnostic = Diagnostic::new(message);
+    if line == 0 {
+        return diagnostic;
+    }
+
+    let span = Span {
+        line,
+        column: column.max(1),
+        len: 1,
+    };
+    diagnostic.with_span(span, source)
+}
+
+// serde_json and serde_yaml append " at line X column Y" to their messages
+fn strip_location(message: &str) -> String {
+    match message.rfind(" at line ") {
+        Some(end) => message[..end].to_string(),
+        None => message.to_string(),
+    }
+}
+
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(ConfigFormat::from_path("commands.json"), ConfigFormat::Json);
+        assert_eq!(ConfigFormat::from_path("commands.jsonc"), ConfigFormat::Json5);
+        assert_eq!(ConfigFormat::from_path("commands.TOML"), ConfigFormat::Toml);
+        assert_eq!(ConfigFormat::from_path("commands.yml"), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path("commands"), ConfigFormat::Json);
+    }
+
+    #[test]
+    fn test_parse_each_format() {
+        let jsonc = r#"{
+            // Comments and trailing commas are fine here
+            "version": "1.0",
+            "commands": [
+                { "name": "build", "description": "Build", "executable": "cargo", "args": ["build",], },
+            ],
+        }"#;
+        let toml = r#"
+            version = "1.0"
+
+            # Comments work in TOML too
+            [[commands]]
+            name = "build"
+            description = "Build"
+            executable = "cargo"
+            args = ["build"]
+            aliases = ["b"]
+        "#;
+        let yaml = "
+version: '1.0'
+commands:
+  - name: build
+    description: Build
+    executable: cargo
+    args: [build]
+";
+
+        for (format, source) in [
+            (ConfigFormat::Json5, jsonc),
+            (ConfigFormat::Toml, toml),
+            (ConfigFormat::Yaml, yaml),
+        ] {
+            let config = format.parse(source).unwrap();
+            assert_eq!(config.commands[0].name, "build");
+            assert_eq!(config.commands[0].args, vec!["build".to_string()]);
+        }
+    }
+
+    #[test]
+    fn test_parse_error_locations() {
+        let line = |result: Result<CommandConfig, ConfigError>| match result {
+            Err(ConfigError::Invalid(problems)) => problems[0].span.map(|span| span.line),
+            _ => panic!("expected a located parse error"),
+        };
+
+        assert_eq!(line(ConfigFormat::Toml.parse("version = \"1.0\"\ncommands = [\n  {name = }\n]")), Some(3));
+        assert_eq!(line(ConfigFormat::Yaml.parse("version: '1.0'\ncommands: 3\n")), Some(2));
+        assert_eq!(line(ConfigFormat::Json.parse("{\n  \"version\": \"9.0\"\n}")), Some(2));
+    }
+}
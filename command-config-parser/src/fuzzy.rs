@@ -0,0 +1,175 @@
+// fuzzy.rs
+use std::ops::Range;
+
+// Scoring weights
+const SCORE_MATCH: i64 = 16;
+const BONUS_FIRST_CHAR: i64 = 12;
+const BONUS_BOUNDARY: i64 = 10;
+const BONUS_CAMEL_CASE: i64 = 8;
+const BONUS_CONSECUTIVE: i64 = 6;
+const PENALTY_GAP_START: i64 = 5;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    // Byte ranges of the matched characters in the candidate
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// Matches `pattern` as a case-insensitive subsequence of `candidate`.
+/// Returns `None` when some pattern character cannot be found in order.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern.chars().map(fold_case).collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            ranges: Vec::new(),
+        });
+    }
+
+    let chars: Vec<(usize, char)> = candidate.char_indices().collect();
+    let (m, n) = (pattern.len(), chars.len());
+    if m > n {
+        return None;
+    }
+
+    let folded: Vec<char> = chars.iter().map(|&(_, c)| fold_case(c)).collect();
+    let bonuses: Vec<i64> = (0..n).map(|j| position_bonus(&chars, j)).collect();
+
+    // score[i][j]: best score with pattern[i] matched at candidate[j]
+    // from[i][j]: where pattern[i - 1] was matched on that best path
+    const NONE: i64 = i64::MIN / 2;
+    let mut score = vec![vec![NONE; n]; m];
+    let mut from = vec![vec![usize::MAX; n]; m];
+
+    for i in 0..m {
+        // Best predecessor for a match at j that leaves a gap after it
+        let mut carry = NONE;
+        let mut carry_from = usize::MAX;
+
+        for j in i..n {
+            if i > 0 && j >= 2 {
+                let opened = score[i - 1][j - 2] - PENALTY_GAP_START;
+                if opened >= carry - PENALTY_GAP_EXTENSION {
+                    carry = opened;
+                    carry_from = j - 2;
+                } else {
+                    carry -= PENALTY_GAP_EXTENSION;
+                }
+            }
+
+            if folded[j] != pattern[i] {
+                continue;
+            }
+
+            let base = SCORE_MATCH + bonuses[j];
+            if i == 0 {
+                score[i][j] = base;
+                continue;
+            }
+
+            let adjacent = score[i - 1][j - 1];
+            if adjacent > NONE && adjacent + BONUS_CONSECUTIVE >= carry {
+                score[i][j] = adjacent + BONUS_CONSECUTIVE + base;
+                from[i][j] = j - 1;
+            } else if carry > NONE {
+                score[i][j] = carry + base;
+                from[i][j] = carry_from;
+            }
+        }
+    }
+
+    let (mut end, best) = score[m - 1]
+        .iter()
+        .enumerate()
+        .filter(|(_, &s)| s > NONE)
+        .max_by_key(|&(j, &s)| (s, std::cmp::Reverse(j)))
+        .map(|(j, &s)| (j, s))?;
+
+    // Walk back through the best path to recover matched positions
+    let mut positions = vec![0; m];
+    for i in (0..m).rev() {
+        positions[i] = end;
+        end = from[i][end];
+    }
+
+    // Prefer shorter candidates when everything else is equal
+    let score = best - (n as i64 - m as i64) / 4;
+
+    Some(FuzzyMatch {
+        score,
+        ranges: byte_ranges(candidate, &chars, &positions),
+    })
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn position_bonus(chars: &[(usize, char)], j: usize) -> i64 {
+    if j == 0 {
+        return BONUS_FIRST_CHAR;
+    }
+
+    let prev = chars[j - 1].1;
+    let current = chars[j].1;
+    if !prev.is_alphanumeric() {
+        BONUS_BOUNDARY
+    } else if prev.is_lowercase() && current.is_uppercase()
+        || prev.is_alphabetic() && current.is_numeric()
+    {
+        BONUS_CAMEL_CASE
+    } else {
+        0
+    }
+}
+
+// Merge matched character positions into contiguous byte ranges
+fn byte_ranges(candidate: &str, chars: &[(usize, char)], positions: &[usize]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+
+    for &j in positions {
+        let start = chars[j].0;
+        let end = chars.get(j + 1).map_or(candidate.len(), |&(offset, _)| offset);
+
+        match ranges.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => ranges.push(start..end),
+        }
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, candidate: &str) -> i64 {
+        fuzzy_match(pattern, candidate).unwrap().score
+    }
+
+    #[test]
+    fn test_subsequence_and_ranges() {
+        assert!(fuzzy_match("xyz", "taskmanager").is_none());
+        assert!(fuzzy_match("tm", "taskmanager").is_some());
+
+        let m = fuzzy_match("tmg", "Task Manager").unwrap();
+        assert_eq!(m.ranges, vec![0..1, 5..6, 9..10]);
+
+        // Multi-byte characters keep valid byte offsets
+        let m = fuzzy_match("cé", "café éditeur").unwrap();
+        assert_eq!(m.ranges, vec![0..1, 6..8]);
+    }
+
+    #[test]
+    fn test_ranking() {
+        // Prefix beats scattered, word boundaries and camelCase beat mid-word
+        assert!(score("calc", "calc") > score("calc", "calculator"));
+        assert!(score("calc", "calculator") > score("calc", "excel calendar c"));
+        assert!(score("tm", "Task Manager") > score("tm", "totem"));
+        assert!(score("tm", "TaskManager") > score("tm", "totem"));
+        assert!(score("code", "Visual Studio Code") > score("code", "decoder"));
+    }
+}
@@ -0,0 +1,468 @@
+// hotkey.rs
+use crate::diagnostic::Diagnostic;
+use crate::fuzzy::fuzzy_match;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// A global key combination such as `Ctrl+Shift+Space`, `Super+P` or
+/// `F13`. Parsed case-insensitively; shown in canonical form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HotkeySpec {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+/// A hotkey and what pressing it does.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct HotkeyBinding {
+    /// Key combination, e.g. `Ctrl+Alt+D`.
+    #[schemars(with = "String")]
+    pub keys: HotkeySpec,
+    pub action: HotkeyAction,
+}
+
+/// A hotkey (the leader) followed by plain keys pressed one after
+/// another, such as `Ctrl+Space g s`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeySequence {
+    pub leader: HotkeySpec,
+    pub keys: Vec<Key>,
+}
+
+/// A key sequence and what completing it does.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SequenceBinding {
+    /// Leader hotkey then keys, separated by spaces, e.g. `Ctrl+Space g s`.
+    #[schemars(with = "String")]
+    pub keys: KeySequence,
+    pub action: HotkeyAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HotkeyAction {
+    /// Open the launcher.
+    Launcher,
+    /// Open the launcher listing only this category's commands.
+    Category(String),
+    /// Run this command right away, or open the launcher on it when it
+    /// takes input.
+    Command(String),
+    /// Open the launcher on the clipboard and snippets.
+    Clipboard,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    // The Windows / Command / logo key
+    pub super_key: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    // `A`-`Z` and `0`-`9`, stored uppercase
+    Char(char),
+    // `F1`-`F24`
+    F(u8),
+    Space,
+    Enter,
+    Tab,
+    Escape,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Up,
+    Down,
+    Left,
+    Right,
+    PrintScreen,
+    Pause,
+}
+
+// Accepted spellings, canonical name first
+const MODIFIERS: &[(&str, &[&str])] = &[
+    ("Ctrl", &["ctrl", "control"]),
+    ("Alt", &["alt", "option"]),
+    ("Shift", &["shift"]),
+    ("Super", &["super", "win", "windows", "meta", "cmd", "command"]),
+];
+
+const NAMED_KEYS: &[(Key, &[&str])] = &[
+    (Key::Space, &["space"]),
+    (Key::Enter, &["enter", "return"]),
+    (Key::Tab, &["tab"]),
+    (Key::Escape, &["escape", "esc"]),
+    (Key::Backspace, &["backspace"]),
+    (Key::Delete, &["delete", "del"]),
+    (Key::Insert, &["insert", "ins"]),
+    (Key::Home, &["home"]),
+    (Key::End, &["end"]),
+    (Key::PageUp, &["pageup", "pgup"]),
+    (Key::PageDown, &["pagedown", "pgdn"]),
+    (Key::Up, &["up"]),
+    (Key::Down, &["down"]),
+    (Key::Left, &["left"]),
+    (Key::Right, &["right"]),
+    (Key::PrintScreen, &["printscreen", "prtsc"]),
+    (Key::Pause, &["pause"]),
+];
+
+impl Default for HotkeySpec {
+    // Ctrl+Space, the launcher's hotkey unless configured otherwise
+    fn default() -> Self {
+        Self {
+            modifiers: Modifiers {
+                ctrl: true,
+                ..Modifiers::default()
+            },
+            key: Key::Space,
+        }
+    }
+}
+
+impl Modifiers {
+    pub fn is_empty(&self) -> bool {
+        *self == Modifiers::default()
+    }
+
+    fn set(&mut self, name: &str) -> &mut bool {
+        match name {
+            "Ctrl" => &mut self.ctrl,
+            "Alt" => &mut self.alt,
+            "Shift" => &mut self.shift,
+            _ => &mut self.super_key,
+        }
+    }
+}
+
+impl Key {
+    // Keys that type text, which would be swallowed without a modifier
+    fn types_text(&self) -> bool {
+        matches!(self, Key::Char(_) | Key::Space | Key::Enter | Key::Tab | Key::Backspace)
+    }
+
+    fn parse(word: &str) -> Option<Key> {
+        let lower = word.to_ascii_lowercase();
+        let mut chars = lower.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return c.is_ascii_alphanumeric().then(|| Key::Char(c.to_ascii_uppercase()));
+        }
+        if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+            return (1..=24).contains(&n).then_some(Key::F(n));
+        }
+        NAMED_KEYS
+            .iter()
+            .find(|(_, names)| names.contains(&lower.as_str()))
+            .map(|(key, _)| *key)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char(c) => write!(f, "{}", c),
+            Key::F(n) => write!(f, "F{}", n),
+            key => write!(f, "{:?}", key),
+        }
+    }
+}
+
+impl fmt::Display for HotkeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m = self.modifiers;
+        for (name, held) in [("Ctrl", m.ctrl), ("Alt", m.alt), ("Shift", m.shift), ("Super", m.super_key)] {
+            if held {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl FromStr for HotkeySpec {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        if parts.iter().all(|part| part.is_empty()) {
+            return Err("hotkey is empty".to_string());
+        }
+        if parts.iter().any(|part| part.is_empty()) {
+            return Err(format!("`{}` has an empty part; keys are joined with a single `+`", text));
+        }
+
+        let mut modifiers = Modifiers::default();
+        let mut key = None;
+        for part in &parts {
+            let lower = part.to_ascii_lowercase();
+            if let Some((name, _)) = MODIFIERS.iter().find(|(_, names)| names.contains(&lower.as_str())) {
+                if key.is_some() {
+                    return Err(format!("`{}`: modifiers go before the key", text));
+                }
+                let held = modifiers.set(name);
+                if *held {
+                    return Err(format!("`{}`: `{}` is repeated", text, name));
+                }
+                *held = true;
+                continue;
+            }
+
+            match (Key::parse(part), key) {
+                (Some(parsed), None) => key = Some(parsed),
+                (Some(parsed), Some(first)) => {
+                    return Err(format!("`{}` has more than one key (`{}` and `{}`)", text, first, parsed));
+                }
+                (None, _) => return Err(unknown_key(text, part)),
+            }
+        }
+
+        let Some(key) = key else {
+            return Err(format!("`{}` has no key, only modifiers", text));
+        };
+        let typing = modifiers.is_empty() || modifiers == Modifiers { shift: true, ..Modifiers::default() };
+        if key.types_text() && typing {
+            return Err(format!(
+                "`{}` would take over normal typing; add Ctrl, Alt or Super, or use a key like F13",
+                text
+            ));
+        }
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl KeySequence {
+    // Whether `other` is this sequence or a longer one continuing it
+    pub fn is_prefix_of(&self, other: &KeySequence) -> bool {
+        self.leader == other.leader && other.keys.starts_with(&self.keys)
+    }
+}
+
+// Letters are shown lowercase after the leader, as they are typed
+fn write_sequence_key(f: &mut fmt::Formatter<'_>, key: Key) -> fmt::Result {
+    match key {
+        Key::Char(c) => write!(f, "{}", c.to_ascii_lowercase()),
+        key => write!(f, "{}", key),
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.leader)?;
+        for key in &self.keys {
+            write!(f, " ")?;
+            write_sequence_key(f, *key)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let mut parts = text.split_whitespace();
+        let Some(leader) = parts.next() else {
+            return Err("key sequence is empty".to_string());
+        };
+        let leader: HotkeySpec = leader.parse()?;
+
+        let mut keys = Vec::new();
+        for part in parts {
+            if part.contains('+') {
+                return Err(format!("`{}`: only the first key of a sequence takes modifiers", text));
+            }
+            match Key::parse(part) {
+                Some(key) => keys.push(key),
+                None => return Err(unknown_key(text, part)),
+            }
+        }
+        if keys.is_empty() {
+            return Err(format!(
+                "`{}` has no keys after the leader; add some, e.g. `{} g s`, or bind it under `hotkeys`",
+                text, leader
+            ));
+        }
+        Ok(Self { leader, keys })
+    }
+}
+
+// "unknown key `Spce` in `Ctrl+Spce`; did you mean `Space`?"
+fn unknown_key(text: &str, part: &str) -> String {
+    let names = MODIFIERS
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .chain(NAMED_KEYS.iter().map(|(key, _)| key.to_string()));
+    let guess = names
+        .filter_map(|name| Some((fuzzy_match(part, &name)?.score, name)))
+        .max_by_key(|(score, _)| *score);
+
+    let mut message = format!("unknown key `{}` in `{}`", part, text);
+    match guess {
+        Some((_, name)) => message.push_str(&format!("; did you mean `{}`?", name)),
+        None => message.push_str("; use a letter, digit, F1-F24 or a named key like Space or PageUp"),
+    }
+    message
+}
+
+// The `hotkey`, `hotkeys` and `sequences` settings of a parsed document,
+// checked before deserializing so problems can be located at their values
+pub(crate) fn check(document: &Value) -> Vec<Diagnostic> {
+    let launcher = document.get("hotkey").map(|value| ("hotkey".to_string(), value));
+    let bindings = document
+        .get("hotkeys")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(i, binding)| Some((format!("hotkeys[{}].keys", i), binding.get("keys")?)));
+
+    let mut problems = Vec::new();
+    let mut seen: Vec<(HotkeySpec, String)> = Vec::new();
+    for (key, value) in launcher.into_iter().chain(bindings) {
+        let Some(text) = value.as_str() else {
+            continue;
+        };
+        match text.parse::<HotkeySpec>() {
+            Ok(spec) => match seen.iter().find(|(other, _)| *other == spec) {
+                Some((_, first)) => problems.push(
+                    Diagnostic::new(format!("hotkey `{}` is bound twice", spec))
+                        .with_key(key)
+                        .with_hint(format!("also bound by `{}`", first)),
+                ),
+                None => seen.push((spec, key)),
+            },
+            Err(message) => problems.push(Diagnostic::new(message).with_key(key)),
+        }
+    }
+
+    let sequences = document
+        .get("sequences")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(i, binding)| Some((format!("sequences[{}].keys", i), binding.get("keys")?.as_str()?)));
+    let mut seen: Vec<(KeySequence, String)> = Vec::new();
+    for (key, text) in sequences {
+        match text.parse::<KeySequence>() {
+            Ok(sequence) => match seen.iter().find(|(other, _)| *other == sequence) {
+                Some((_, first)) => problems.push(
+                    Diagnostic::new(format!("sequence `{}` is bound twice", sequence))
+                        .with_key(key)
+                        .with_hint(format!("also bound by `{}`", first)),
+                ),
+                None => seen.push((sequence, key)),
+            },
+            Err(message) => problems.push(Diagnostic::new(message).with_key(key)),
+        }
+    }
+    problems
+}
+
+impl TryFrom<String> for HotkeySpec {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        text.parse()
+    }
+}
+
+impl From<HotkeySpec> for String {
+    fn from(spec: HotkeySpec) -> Self {
+        spec.to_string()
+    }
+}
+
+impl TryFrom<String> for KeySequence {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        text.parse()
+    }
+}
+
+impl From<KeySequence> for String {
+    fn from(sequence: KeySequence) -> Self {
+        sequence.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hotkeys() {
+        let spec: HotkeySpec = "ctrl + shift + space".parse().unwrap();
+        assert!(spec.modifiers.ctrl && spec.modifiers.shift && !spec.modifiers.alt);
+        assert_eq!(spec.key, Key::Space);
+        assert_eq!(spec.to_string(), "Ctrl+Shift+Space");
+
+        assert_eq!("Win+p".parse::<HotkeySpec>().unwrap().to_string(), "Super+P");
+        assert_eq!("F13".parse::<HotkeySpec>().unwrap().key, Key::F(13));
+        assert_eq!("Alt+PgDn".parse::<HotkeySpec>().unwrap().to_string(), "Alt+PageDown");
+        assert_eq!(HotkeySpec::default().to_string(), "Ctrl+Space");
+    }
+
+    #[test]
+    fn test_hotkey_errors() {
+        let error = |text: &str| text.parse::<HotkeySpec>().unwrap_err();
+
+        assert_eq!(error("Ctrl+Spce"), "unknown key `Spce` in `Ctrl+Spce`; did you mean `Space`?");
+        assert_eq!(error("Ctrl+F25"), "unknown key `F25` in `Ctrl+F25`; use a letter, digit, F1-F24 or a named key like Space or PageUp");
+        assert_eq!(error("Ctrl+Shift"), "`Ctrl+Shift` has no key, only modifiers");
+        assert_eq!(error("Ctrl+A+B"), "`Ctrl+A+B` has more than one key (`A` and `B`)");
+        assert_eq!(error("Ctrl+ctrl+K"), "`Ctrl+ctrl+K`: `Ctrl` is repeated");
+        assert_eq!(error("K+Ctrl"), "`K+Ctrl`: modifiers go before the key");
+        assert_eq!(error("Ctrl++"), "`Ctrl++` has an empty part; keys are joined with a single `+`");
+        assert_eq!(error(" "), "hotkey is empty");
+        assert!(error("Shift+P").contains("would take over normal typing"));
+
+        let document = serde_json::json!({
+            "hotkey": "Ctrl+Space",
+            "hotkeys": [{ "keys": "ctrl+space", "action": "clipboard" }, { "keys": "Ctrl+", "action": "launcher" }],
+        });
+        let problems = check(&document);
+        assert_eq!(problems[0].message, "hotkey `Ctrl+Space` is bound twice");
+        assert_eq!(problems[0].key.as_deref(), Some("hotkeys[0].keys"));
+        assert_eq!(problems[1].key.as_deref(), Some("hotkeys[1].keys"));
+    }
+
+    #[test]
+    fn test_parse_sequences() {
+        let sequence: KeySequence = "ctrl+space  G s".parse().unwrap();
+        assert_eq!(sequence.leader, HotkeySpec::default());
+        assert_eq!(sequence.keys, [Key::Char('G'), Key::Char('S')]);
+        assert_eq!(sequence.to_string(), "Ctrl+Space g s");
+        assert!("Ctrl+Space g".parse::<KeySequence>().unwrap().is_prefix_of(&sequence));
+        assert!(!"Super+Space g".parse::<KeySequence>().unwrap().is_prefix_of(&sequence));
+
+        let error = |text: &str| text.parse::<KeySequence>().unwrap_err();
+        assert_eq!(error("Ctrl+Space g Ctrl+S"), "`Ctrl+Space g Ctrl+S`: only the first key of a sequence takes modifiers");
+        assert_eq!(error("Ctrl+Space gs"), "unknown key `gs` in `Ctrl+Space gs`; use a letter, digit, F1-F24 or a named key like Space or PageUp");
+        assert!(error("ctrl+space").starts_with("`ctrl+space` has no keys after the leader"));
+        assert!(error("g s").contains("would take over normal typing"));
+
+        let document = serde_json::json!({
+            "sequences": [
+                { "keys": "Ctrl+Space g s", "action": { "command": "status" } },
+                { "keys": "ctrl+space G S", "action": "clipboard" },
+            ],
+        });
+        let problems = check(&document);
+        assert_eq!(problems[0].message, "sequence `Ctrl+Space g s` is bound twice");
+        assert_eq!(problems[0].key.as_deref(), Some("sequences[1].keys"));
+    }
+}
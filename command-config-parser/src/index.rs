@@ -0,0 +1,270 @@
+// index.rs
+use std::collections::{BTreeMap, HashSet};
+
+/// A trie over command names and aliases. Every key is indexed from each of
+/// its token starts ("git-status" under "git-status" and "status",
+/// "TaskManager" under "taskmanager" and "manager"), so prefix, token-prefix
+/// and fuzzy lookups never have to scan all keys.
+#[derive(Debug, Default)]
+pub struct PrefixIndex {
+    root: Node,
+    keys: HashSet<String>,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: BTreeMap<char, Node>,
+    // Keys whose indexed suffix ends here, with the token it starts at
+    entries: Vec<(String, usize)>,
+    // Characters on any edge below this node, used to prune fuzzy walks
+    mask: u64,
+}
+
+impl PrefixIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+
+    pub fn clear(&mut self) {
+        self.root = Node::default();
+        self.keys.clear();
+    }
+
+    // Returns false if the key was already indexed
+    pub fn insert(&mut self, key: &str) -> bool {
+        if !self.keys.insert(key.to_string()) {
+            return false;
+        }
+
+        for (token, start) in token_starts(key).into_iter().enumerate() {
+            let path: Vec<char> = key[start..].chars().map(fold_case).collect();
+            let mut node = &mut self.root;
+            for (i, &c) in path.iter().enumerate() {
+                node.mask |= path[i..].iter().fold(0, |mask, &c| mask | char_bit(c));
+                node = node.children.entry(c).or_default();
+            }
+            node.entries.push((key.to_string(), token));
+        }
+
+        true
+    }
+
+    // Returns false if the key was not indexed
+    pub fn remove(&mut self, key: &str) -> bool {
+        if !self.keys.remove(key) {
+            return false;
+        }
+
+        for start in token_starts(key) {
+            let path: Vec<char> = key[start..].chars().map(fold_case).collect();
+            remove_entry(&mut self.root, &path, key);
+        }
+
+        true
+    }
+
+    /// Keys starting with `prefix`, case-insensitively.
+    pub fn prefix(&self, prefix: &str) -> Vec<&str> {
+        let mut keys = Vec::new();
+        if let Some(node) = self.find(prefix) {
+            node.collect(&mut |key, token| {
+                if token == 0 {
+                    keys.push(key);
+                }
+            });
+        }
+        keys
+    }
+
+    /// Keys with any token starting with `prefix` ("stat" finds "git-status").
+    pub fn token_prefix(&self, prefix: &str) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        let mut keys = Vec::new();
+        if let Some(node) = self.find(prefix) {
+            node.collect(&mut |key, _| {
+                if seen.insert(key) {
+                    keys.push(key);
+                }
+            });
+        }
+        keys
+    }
+
+    /// Keys containing `pattern` as a case-insensitive subsequence. These are
+    /// the candidates worth scoring with [`crate::fuzzy::fuzzy_match`].
+    pub fn fuzzy(&self, pattern: &str) -> Vec<&str> {
+        let pattern: Vec<char> = pattern.chars().map(fold_case).collect();
+        let mut seen = HashSet::new();
+        let mut keys = Vec::new();
+
+        self.root.walk_fuzzy(&pattern, &mut |key| {
+            if seen.insert(key) {
+                keys.push(key);
+            }
+        });
+
+        keys
+    }
+
+    fn find(&self, prefix: &str) -> Option<&Node> {
+        prefix
+            .chars()
+            .map(fold_case)
+            .try_fold(&self.root, |node, c| node.children.get(&c))
+    }
+}
+
+impl Node {
+    fn collect<'a>(&'a self, visit: &mut impl FnMut(&'a str, usize)) {
+        for (key, token) in &self.entries {
+            visit(key, *token);
+        }
+        for child in self.children.values() {
+            child.collect(visit);
+        }
+    }
+
+    fn walk_fuzzy<'a>(&'a self, pattern: &[char], visit: &mut impl FnMut(&'a str)) {
+        if pattern.is_empty() {
+            self.collect(&mut |key, _| visit(key));
+            return;
+        }
+
+        let needed = pattern.iter().fold(0, |mask, &c| mask | char_bit(c));
+        if needed & !self.mask != 0 {
+            return;
+        }
+
+        for (&c, child) in &self.children {
+            let rest = if c == pattern[0] { &pattern[1..] } else { pattern };
+            child.walk_fuzzy(rest, visit);
+        }
+    }
+
+    fn recompute_mask(&mut self) {
+        self.mask = self
+            .children
+            .iter()
+            .fold(0, |mask, (&c, child)| mask | char_bit(c) | child.mask);
+    }
+}
+
+// Remove `key` from the node at `path`, pruning nodes that become empty
+fn remove_entry(node: &mut Node, path: &[char], key: &str) {
+    match path.split_first() {
+        None => node.entries.retain(|(entry, _)| entry != key),
+        Some((c, rest)) => {
+            if let Some(child) = node.children.get_mut(c) {
+                remove_entry(child, rest, key);
+                if child.entries.is_empty() && child.children.is_empty() {
+                    node.children.remove(c);
+                }
+            }
+            node.recompute_mask();
+        }
+    }
+}
+
+// Byte offsets where tokens start: the beginning, after separators,
+// at camelCase humps and where digits follow letters
+fn token_starts(key: &str) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut prev: Option<char> = None;
+
+    for (offset, c) in key.char_indices() {
+        let boundary = match prev {
+            None => true,
+            Some(p) => {
+                !p.is_alphanumeric() && c.is_alphanumeric()
+                    || p.is_lowercase() && c.is_uppercase()
+                    || p.is_alphabetic() && c.is_numeric()
+            }
+        };
+        if boundary {
+            starts.push(offset);
+        }
+        prev = Some(c);
+    }
+
+    starts
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn char_bit(c: char) -> u64 {
+    let bit = match c {
+        'a'..='z' => c as u32 - 'a' as u32,
+        '0'..='9' => 26 + c as u32 - '0' as u32,
+        _ => 36 + c as u32 % 28,
+    };
+    1 << bit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> PrefixIndex {
+        let mut index = PrefixIndex::new();
+        for key in ["build", "b", "git-status", "git-stash", "TaskManager", "deploy"] {
+            index.insert(key);
+        }
+        index
+    }
+
+    #[test]
+    fn test_prefix_and_token_prefix() {
+        let index = sample();
+
+        assert_eq!(index.prefix("GIT-st"), vec!["git-stash", "git-status"]);
+        assert_eq!(index.prefix("b"), vec!["b", "build"]);
+        assert!(index.prefix("status").is_empty());
+
+        assert_eq!(index.token_prefix("stat"), vec!["git-status"]);
+        assert_eq!(index.token_prefix("man"), vec!["TaskManager"]);
+    }
+
+    #[test]
+    fn test_fuzzy_candidates() {
+        let index = sample();
+
+        let mut keys = index.fuzzy("gst");
+        keys.sort();
+        assert_eq!(keys, vec!["git-stash", "git-status"]);
+        assert_eq!(index.fuzzy("tm"), vec!["TaskManager"]);
+        assert!(index.fuzzy("xyz").is_empty());
+        assert_eq!(index.fuzzy("").len(), 6);
+    }
+
+    #[test]
+    fn test_incremental_insert_and_remove() {
+        let mut index = sample();
+
+        assert!(!index.insert("build"));
+        assert!(index.remove("git-status"));
+        assert!(!index.remove("git-status"));
+        assert_eq!(index.len(), 5);
+
+        assert!(index.token_prefix("stat").is_empty());
+        assert_eq!(index.prefix("git"), vec!["git-stash"]);
+        // Pruning masks are updated on removal
+        assert!(index.fuzzy("gtu").is_empty());
+
+        index.insert("git-status");
+        assert_eq!(index.token_prefix("stat"), vec!["git-status"]);
+    }
+}
@@ -0,0 +1,33 @@
+pub mod config;
+pub mod danger;
+pub mod diagnostic;
+pub mod engines;
+pub mod env;
+pub mod exec;
+pub mod format;
+pub mod fuzzy;
+pub mod hotkey;
+pub mod index;
+pub mod migrate;
+pub mod params;
+pub mod schema;
+pub mod search;
+pub mod watch;
+
+pub use config::{
+    create_sample_config, Command, CommandConfig, CommandRegistry, ConfigError, Include, KillSignal, Snippet,
+    DEFAULT_CONFIG_FILES,
+};
+pub use danger::{risks, DangerLevel};
+pub use diagnostic::{Diagnostic, SourceMap, Span};
+pub use engines::{load_engines, SearchEngine};
+pub use env::{expand_vars, system_vars, EnvMode};
+pub use exec::ExecMode;
+pub use format::ConfigFormat;
+pub use fuzzy::{fuzzy_match, FuzzyMatch};
+pub use hotkey::{HotkeyAction, HotkeyBinding, HotkeySpec, Key, KeySequence, Modifiers, SequenceBinding};
+pub use index::PrefixIndex;
+pub use migrate::{migrate, upgrade_file, CURRENT_VERSION};
+pub use params::{Param, ParamKind, Placeholder};
+pub use schema::{config_schema, KNOWN_CATEGORIES};
+pub use watch::ConfigWatcher;
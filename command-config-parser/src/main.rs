@@ -0,0 +1,56 @@
+use command_config_parser::{config_schema, upgrade_file, CommandRegistry};
+use std::env;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    // `--print-schema` emits the JSON Schema for editors
+    if args.first().is_some_and(|arg| arg == "--print-schema") {
+        let schema = config_schema();
+        println!("{}", serde_json::to_string_pretty(&schema).expect("schema serializes"));
+        return;
+    }
+
+    // `--upgrade <file>` rewrites an outdated file in the current schema
+    if args.first().is_some_and(|arg| arg == "--upgrade") {
+        let [_, path] = args.as_slice() else {
+            eprintln!("usage: command-config-parser --upgrade <file>");
+            process::exit(2);
+        };
+        match upgrade_file(path) {
+            Ok(Some(backup)) => println!("Upgraded {} (backup at {})", path, backup.display()),
+            Ok(None) => println!("{} is already up to date", path),
+            Err(err) => {
+                eprint!("{}", err.render());
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // An explicit file, or the system/user/project layers otherwise
+    let loaded = match args.first() {
+        Some(path) => CommandRegistry::load_from_file(path),
+        None => CommandRegistry::load_from_default(),
+    };
+
+    let registry = match loaded {
+        Ok(registry) => registry,
+        Err(err) => {
+            eprint!("{}", err.render());
+            process::exit(1);
+        }
+    };
+
+    for source in registry.sources() {
+        println!("# {}", source.display());
+    }
+
+    for category in registry.get_categories() {
+        println!("[{}]", category);
+        for command in registry.get_commands_by_category(&category) {
+            println!("  {:<12} {}", command.name, command.description);
+        }
+    }
+}
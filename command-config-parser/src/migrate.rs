@@ -0,0 +1,305 @@
+// migrate.rs
+use crate::config::ConfigError;
+use crate::diagnostic::Diagnostic;
+use crate::format::ConfigFormat;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The schema version this crate reads and writes.
+pub const CURRENT_VERSION: &str = "1.0";
+
+// Files without a version (including the original bare array of
+// `{ "name", "command" }` entries) are the oldest schema
+const LEGACY_VERSION: &str = "0.1";
+
+type Migration = fn(&mut Map<String, Value>);
+
+// Each step upgrades a document from the first version to the second
+const MIGRATIONS: &[(&str, &str, Migration)] = &[
+    ("0.1", "0.2", split_command_lines),
+    ("0.2", "1.0", split_args),
+];
+
+/// Upgrade a parsed document to [`CURRENT_VERSION`] in place.
+///
+/// Returns the version the document was written in when it had to be
+/// migrated, or `None` if it was already current.
+pub fn migrate(document: &mut Value) -> Result<Option<String>, ConfigError> {
+    if let Value::Array(commands) = document {
+        let commands = std::mem::take(commands);
+        *document = serde_json::json!({ "version": LEGACY_VERSION, "commands": commands });
+    }
+
+    let Value::Object(config) = document else {
+        return Err(invalid(Diagnostic::new("configuration must be an object")));
+    };
+
+    let original = match config.get("version") {
+        None => LEGACY_VERSION.to_string(),
+        Some(Value::String(version)) => normalize(version),
+        // YAML and TOML read an unquoted `1.0` as a number
+        Some(Value::Number(version)) => normalize(&version.to_string()),
+        Some(_) => {
+            return Err(invalid(
+                Diagnostic::new("`version` must be a string")
+                    .with_key("version")
+                    .with_hint(format!("use \"version\": \"{}\"", CURRENT_VERSION)),
+            ));
+        }
+    };
+
+    let mut version = original.clone();
+    while version != CURRENT_VERSION {
+        let Some((_, to, step)) = MIGRATIONS.iter().find(|(from, _, _)| *from == version) else {
+            return Err(invalid(unsupported(&version)));
+        };
+        if let Some(Value::Array(commands)) = config.get_mut("commands") {
+            for command in commands.iter_mut().filter_map(Value::as_object_mut) {
+                step(command);
+            }
+        }
+        version = to.to_string();
+    }
+
+    let current = Value::String(CURRENT_VERSION.to_string());
+    if config.get("version") == Some(&current) {
+        return Ok(None);
+    }
+    config.insert("version".to_string(), current);
+    Ok(Some(original))
+}
+
+/// Rewrite an outdated configuration file in the current schema, keeping
+/// the original next to it as `<file>.bak`.
+///
+/// Returns the backup path, or `None` if the file was already current.
+/// Comments in JSONC/JSON5 files do not survive the rewrite.
+pub fn upgrade_file<P: AsRef<Path>>(path: P) -> Result<Option<PathBuf>, ConfigError> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(ConfigError::FileNotFound);
+    }
+
+    let format = ConfigFormat::from_path(path);
+    let content = fs::read_to_string(path)?;
+    let mut document = format.parse_value(&content).map_err(|err| err.with_path(path))?;
+
+    if migrate(&mut document).map_err(|err| err.with_path(path))?.is_none() {
+        return Ok(None);
+    }
+
+    let config = serde_json::from_value(document)?;
+    let text = format.serialize(&config)?;
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+    fs::copy(path, &backup)?;
+    fs::write(path, text)?;
+
+    Ok(Some(backup))
+}
+
+// "1" and "1.0" are the same version
+fn normalize(version: &str) -> String {
+    let version = version.trim();
+    if version.contains('.') {
+        version.to_string()
+    } else {
+        format!("{}.0", version)
+    }
+}
+
+fn invalid(diagnostic: Diagnostic) -> ConfigError {
+    ConfigError::Invalid(vec![diagnostic])
+}
+
+fn unsupported(version: &str) -> Diagnostic {
+    let newer = parse_version(version)
+        .zip(parse_version(CURRENT_VERSION))
+        .is_some_and(|(version, current)| version > current);
+
+    let diagnostic = Diagnostic::new(format!("unsupported configuration version `{}`", version))
+        .with_key("version");
+    if newer {
+        diagnostic.with_hint(format!(
+            "this file needs a newer totalcontrol; the newest version supported here is {}",
+            CURRENT_VERSION
+        ))
+    } else {
+        diagnostic.with_hint(format!("the current version is {}", CURRENT_VERSION))
+    }
+}
+
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let (major, minor) = version.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+// 0.1 → 0.2: `command` held a whole command line and `alias` a single
+// alias; split the line into `executable` plus an `args` string
+fn split_command_lines(command: &mut Map<String, Value>) {
+    if let Some(Value::String(line)) = command.remove("command") {
+        let line = line.trim();
+        let (executable, rest) = match first_word_end(line) {
+            Some(end) => (&line[..end], line[end..].trim_start()),
+            None => (line, ""),
+        };
+        let executable: String = executable.chars().filter(|c| *c != '"').collect();
+        // `start` is built into cmd rather than a program, and takes a
+        // first quoted argument as the window title
+        let rest = if executable.eq_ignore_ascii_case("start") {
+            command.entry("exec").or_insert(Value::String("cmd".to_string()));
+            format!("'\"\"' {}", rest)
+        } else {
+            rest.to_string()
+        };
+        command.entry("executable").or_insert(Value::String(executable));
+        command.entry("args").or_insert(Value::String(rest));
+    }
+
+    for (old, new) in [("alias", "aliases"), ("cwd", "working_dir"), ("env", "env_vars")] {
+        if let Some(value) = command.remove(old) {
+            command.entry(new).or_insert(value);
+        }
+    }
+    if let Some(Value::String(alias)) = command.get("aliases") {
+        let aliases = vec![Value::String(alias.clone())];
+        command.insert("aliases".to_string(), Value::Array(aliases));
+    }
+
+    command
+        .entry("description")
+        .or_insert(Value::String(String::new()));
+}
+
+// 0.2 → 1.0: `args` became a list instead of one string
+fn split_args(command: &mut Map<String, Value>) {
+    let args = match command.remove("args") {
+        Some(Value::String(line)) => split_words(&line).into_iter().map(Value::String).collect(),
+        Some(args) => {
+            command.insert("args".to_string(), args);
+            return;
+        }
+        None => Vec::new(),
+    };
+    command.insert("args".to_string(), Value::Array(args));
+}
+
+// Byte offset where the first word of `line` ends, honouring double quotes
+// so `"C:\Program Files\app.exe" --flag` keeps the path together
+fn first_word_end(line: &str) -> Option<usize> {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+// Split on whitespace outside single or double quotes. Backslashes are kept
+// as they are, since legacy files mostly hold Windows paths.
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_migrate_legacy_array() {
+        let mut document = json!([
+            { "name": "notepad", "command": "notepad.exe" },
+            { "name": "google", "command": "start https://www.google.com", "alias": "g" },
+            { "name": "code", "command": "\"C:\\Program Files\\Code\\code.exe\" --new-window 'my project'" }
+        ]);
+
+        assert_eq!(migrate(&mut document).unwrap(), Some("0.1".to_string()));
+        assert_eq!(document["version"], "1.0");
+
+        let commands = &document["commands"];
+        assert_eq!(commands[0]["executable"], "notepad.exe");
+        assert_eq!(commands[0]["args"], json!([]));
+        assert_eq!(commands[1]["executable"], "start");
+        assert_eq!(commands[1]["exec"], "cmd");
+        assert_eq!(commands[1]["args"], json!(["\"\"", "https://www.google.com"]));
+        assert_eq!(commands[1]["aliases"], json!(["g"]));
+        assert_eq!(commands[2]["executable"], "C:\\Program Files\\Code\\code.exe");
+        assert_eq!(commands[2]["args"], json!(["--new-window", "my project"]));
+    }
+
+    #[test]
+    fn test_migrate_versions() {
+        let mut current = json!({ "version": "1.0", "commands": [] });
+        assert_eq!(migrate(&mut current).unwrap(), None);
+
+        let mut numeric = json!({ "version": 1.0, "commands": [] });
+        assert_eq!(migrate(&mut numeric).unwrap(), Some("1.0".to_string()));
+        assert_eq!(numeric["version"], "1.0");
+
+        let mut old = json!({ "version": "0.2", "commands": [
+            { "name": "ls", "description": "", "executable": "ls", "args": "-la /tmp" }
+        ] });
+        assert_eq!(migrate(&mut old).unwrap(), Some("0.2".to_string()));
+        assert_eq!(old["commands"][0]["args"], json!(["-la", "/tmp"]));
+
+        let mut future = json!({ "version": "2.0", "commands": [] });
+        let Err(ConfigError::Invalid(problems)) = migrate(&mut future) else {
+            panic!("expected an unsupported version");
+        };
+        assert_eq!(problems[0].message, "unsupported configuration version `2.0`");
+        assert!(problems[0].hint.as_ref().unwrap().contains("newer totalcontrol"));
+    }
+
+    #[test]
+    fn test_upgrade_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("commands.json");
+        fs::write(&path, r#"[{ "name": "calc", "command": "calc.exe" }]"#).unwrap();
+
+        let backup = upgrade_file(&path).unwrap().unwrap();
+        assert_eq!(backup, dir.path().join("commands.json.bak"));
+        assert!(fs::read_to_string(&backup).unwrap().contains("\"command\""));
+
+        let config: crate::CommandConfig = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(config.version, CURRENT_VERSION);
+        assert_eq!(config.commands[0].executable, "calc.exe");
+
+        // Already current: nothing to do
+        assert_eq!(upgrade_file(&path).unwrap(), None);
+    }
+}
@@ -0,0 +1,256 @@
+// params.rs
+use crate::config::Command;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Placeholders filled in by the launcher rather than declared parameters.
+pub const BUILTIN_PLACEHOLDERS: &[&str] = &["query", "clipboard", "selection", "date"];
+
+/// A value a command asks for, referenced as `{name}` in its arguments,
+/// working directory or environment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Param {
+    /// Name used in `{name}` placeholders.
+    pub name: String,
+    /// What kind of value is expected.
+    #[serde(rename = "type", default)]
+    pub kind: ParamKind,
+    /// Shown when asking for the value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Allowed values for a `choice` parameter.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+    /// Used when no value is given; without one the launcher asks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamKind {
+    #[default]
+    String,
+    Choice,
+    Path,
+    Number,
+}
+
+impl Param {
+    // An undeclared `{name}` placeholder, taken as free text
+    pub fn implicit(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: ParamKind::String,
+            prompt: None,
+            choices: Vec::new(),
+            default: None,
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        self.prompt.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Check a value given for this parameter, returning it as it should be
+    /// substituted.
+    pub fn check(&self, value: &str) -> Result<String, String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(format!("`{}` needs a value", self.name));
+        }
+
+        match self.kind {
+            ParamKind::String => Ok(value.to_string()),
+            ParamKind::Choice => self
+                .choices
+                .iter()
+                .find(|choice| choice.eq_ignore_ascii_case(value))
+                .cloned()
+                .ok_or_else(|| format!("`{}` must be one of {}", self.name, self.choices.join(", "))),
+            ParamKind::Number => value
+                .parse::<f64>()
+                .map(|_| value.to_string())
+                .map_err(|_| format!("`{}` must be a number", self.name)),
+            ParamKind::Path if value == "~" || value.starts_with("~/") || Path::new(value).is_absolute() => {
+                Ok(value.to_string())
+            }
+            // Relative paths are resolved against the launcher's directory
+            ParamKind::Path => Ok(std::env::current_dir()
+                .map(|dir| dir.join(value).display().to_string())
+                .unwrap_or_else(|_| value.to_string())),
+        }
+    }
+}
+
+impl Command {
+    /// Fields that may hold placeholders, keyed like `args[1]`,
+    /// `working_dir` or `env_vars.NAME`.
+    pub fn templates(&self) -> Vec<(String, &str)> {
+        let mut fields: Vec<(String, &str)> = self
+            .args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| (format!("args[{}]", i), arg.as_str()))
+            .collect();
+
+        if let Some(ref dir) = self.working_dir {
+            fields.push(("working_dir".to_string(), dir));
+        }
+        if let Some(ref env_vars) = self.env_vars {
+            let mut names: Vec<&String> = env_vars.keys().collect();
+            names.sort();
+            for name in names {
+                fields.push((format!("env_vars.{}", name), &env_vars[name]));
+            }
+        }
+        fields
+    }
+
+    /// Every parameter the command needs: the declared ones, then any
+    /// other non built-in placeholder in order of first use.
+    pub fn parameters(&self) -> Vec<Param> {
+        let mut params = self.params.clone();
+        for (_, text) in self.templates() {
+            for placeholder in placeholders(text) {
+                let known = BUILTIN_PLACEHOLDERS.contains(&placeholder.name.as_str())
+                    || params.iter().any(|param| param.name == placeholder.name);
+                if !known {
+                    params.push(Param::implicit(&placeholder.name));
+                }
+            }
+        }
+        params
+    }
+}
+
+/// A `{name}` or `{name:format}` reference in a command field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    pub name: String,
+    pub format: Option<String>,
+}
+
+// One piece of a field: literal text or a placeholder
+pub(crate) enum Segment<'a> {
+    Text(&'a str),
+    Placeholder(Placeholder),
+}
+
+// Only `{name}` and `{name:format}` are placeholders. Other braces, such
+// as `awk '{print $1}'` or `${HOME}`, stay as they are; `{{` and `}}`
+// write a literal brace where a placeholder would otherwise be read.
+pub(crate) fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut literal = 0;
+    let mut pos = 0;
+
+    while let Some(offset) = text[pos..].find(['{', '}']) {
+        let start = pos + offset;
+        let brace = &text[start..start + 1];
+        let after = &text[start + 1..];
+
+        if after.starts_with(brace) {
+            segments.push(Segment::Text(&text[literal..start + 1]));
+            pos = start + 2;
+            literal = pos;
+            continue;
+        }
+
+        let placeholder = (brace == "{" && !text[..start].ends_with('$'))
+            .then(|| after.find('}'))
+            .flatten()
+            .and_then(|end| parse_placeholder(&after[..end]).map(|p| (p, end)));
+
+        match placeholder {
+            Some((placeholder, end)) => {
+                segments.push(Segment::Text(&text[literal..start]));
+                segments.push(Segment::Placeholder(placeholder));
+                pos = start + end + 2;
+                literal = pos;
+            }
+            None => pos = start + 1,
+        }
+    }
+
+    segments.push(Segment::Text(&text[literal..]));
+    segments
+}
+
+fn parse_placeholder(inner: &str) -> Option<Placeholder> {
+    let (name, format) = match inner.split_once(':') {
+        Some((name, format)) => (name, Some(format.to_string())),
+        None => (inner, None),
+    };
+    let valid = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    valid.then(|| Placeholder {
+        name: name.to_string(),
+        format,
+    })
+}
+
+/// The placeholders used in `text`, in order.
+pub fn placeholders(text: &str) -> Vec<Placeholder> {
+    segments(text)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Placeholder(placeholder) => Some(placeholder),
+            Segment::Text(_) => None,
+        })
+        .collect()
+}
+
+/// Replace every placeholder in `text` with what `resolve` returns for it.
+pub fn expand<E>(
+    text: &str,
+    mut resolve: impl FnMut(&Placeholder) -> Result<String, E>,
+) -> Result<String, E> {
+    let mut out = String::with_capacity(text.len());
+    for segment in segments(text) {
+        match segment {
+            Segment::Text(text) => out.push_str(text),
+            Segment::Placeholder(placeholder) => out.push_str(&resolve(&placeholder)?),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholders_and_expand() {
+        let text = "deploy {env} --at {date:%Y-%m-%d} {{literal}}";
+        let found = placeholders(text);
+        assert_eq!(found[0], Placeholder { name: "env".to_string(), format: None });
+        assert_eq!(found[1].format.as_deref(), Some("%Y-%m-%d"));
+        assert_eq!(found.len(), 2);
+
+        let expanded = expand::<()>(text, |p| Ok(p.name.to_uppercase())).unwrap();
+        assert_eq!(expanded, "deploy ENV --at DATE {literal}");
+
+        // Shell syntax is left alone
+        for text in ["awk '{print $1}'", "echo {1..3} {a,b}", "${HOME}", "{env", "}"] {
+            assert!(placeholders(text).is_empty(), "{}", text);
+            assert_eq!(expand::<()>(text, |_| Ok(String::new())).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn test_check_values() {
+        let mut env = Param::implicit("env");
+        env.kind = ParamKind::Choice;
+        env.choices = vec!["staging".to_string(), "prod".to_string()];
+        assert_eq!(env.check("PROD"), Ok("prod".to_string()));
+        assert!(env.check("dev").is_err());
+
+        let mut port = Param::implicit("port");
+        port.kind = ParamKind::Number;
+        assert_eq!(port.check(" 8080 "), Ok("8080".to_string()));
+        assert_eq!(port.check("http"), Err("`port` must be a number".to_string()));
+        assert!(port.check("").is_err());
+    }
+}
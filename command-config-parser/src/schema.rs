@@ -0,0 +1,171 @@
+// schema.rs
+use crate::config::CommandConfig;
+use crate::diagnostic::Diagnostic;
+use jsonschema::paths::LocationSegment;
+use jsonschema::error::ValidationErrorKind;
+use jsonschema::Validator;
+use schemars::{json_schema, Schema, SchemaGenerator};
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// Categories editors offer for completion. Other names are still allowed.
+pub const KNOWN_CATEGORIES: &[&str] = &[
+    "development",
+    "deployment",
+    "files",
+    "media",
+    "system",
+    "web",
+];
+
+// Names and executables need at least one non-space character
+pub(crate) const NON_BLANK: &str = r"\S";
+// Aliases are typed as a single word
+pub(crate) const ALIAS: &str = r"^\S+$";
+
+/// JSON Schema for configuration files, generated from [`CommandConfig`].
+/// Loading checks files against this same schema.
+pub fn config_schema() -> Value {
+    schemars::schema_for!(CommandConfig).to_value()
+}
+
+// Every schema violation in a parsed (and migrated) document
+pub(crate) fn check(document: &Value) -> Vec<Diagnostic> {
+    static VALIDATOR: OnceLock<Validator> = OnceLock::new();
+    let validator = VALIDATOR.get_or_init(|| {
+        jsonschema::validator_for(&config_schema()).expect("generated schema is valid")
+    });
+
+    validator
+        .iter_errors(document)
+        .map(|error| {
+            let key = key(&error.instance_path);
+            let message = match error.kind {
+                ValidationErrorKind::Pattern { ref pattern } => pattern_message(pattern, &key, &error.instance),
+                _ => error.to_string(),
+            };
+            let diagnostic = Diagnostic::new(message);
+            if key.is_empty() {
+                diagnostic
+            } else {
+                diagnostic.with_key(key)
+            }
+        })
+        .collect()
+}
+
+// The validator rewrites `\S` into a character class, so the patterns are
+// told apart by shape: only ALIAS is anchored
+fn pattern_message(pattern: &str, key: &str, value: &Value) -> String {
+    if !pattern.starts_with('^') {
+        return format!("{} is empty", describe(key));
+    }
+    if key.contains(".aliases[") {
+        format!("alias {} contains whitespace", value)
+    } else if key.ends_with(".keyword") {
+        format!("search keyword {} must be a single word", value)
+    } else {
+        format!("{} must be a single word", describe(key))
+    }
+}
+
+pub(crate) fn aliases(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "description": "Other names the command can be run by.",
+        "type": ["array", "null"],
+        "items": { "type": "string", "pattern": ALIAS },
+    })
+}
+
+pub(crate) fn category(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "description": "Group the command is listed under.",
+        "anyOf": [
+            { "enum": KNOWN_CATEGORIES },
+            { "type": ["string", "null"] },
+        ],
+    })
+}
+
+// `/commands/0/name` as `commands[0].name`, matching `SourceMap` keys
+fn key(path: &jsonschema::paths::Location) -> String {
+    let mut key = String::new();
+    for segment in path {
+        match segment {
+            LocationSegment::Property(name) if key.is_empty() => key.push_str(name),
+            LocationSegment::Property(name) => {
+                key.push('.');
+                key.push_str(name);
+            }
+            LocationSegment::Index(index) => key.push_str(&format!("[{}]", index)),
+        }
+    }
+    key
+}
+
+// `commands[2].executable` reads as "command executable"
+fn describe(key: &str) -> String {
+    match key.rsplit_once('.') {
+        Some((parent, field)) if parent.starts_with("commands[") => format!("command {}", field),
+        _ => format!("`{}`", key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_schema_describes_commands() {
+        let schema = config_schema();
+        let command = &schema["$defs"]["Command"];
+
+        assert_eq!(
+            command["properties"]["name"]["description"],
+            "Unique name the command is run by."
+        );
+        assert_eq!(command["properties"]["aliases"]["items"]["pattern"], ALIAS);
+        assert_eq!(
+            command["properties"]["category"]["anyOf"][0]["enum"][0],
+            "development"
+        );
+
+        let required = command["required"].as_array().unwrap();
+        assert!(required.contains(&json!("executable")));
+        assert!(!required.contains(&json!("aliases")));
+    }
+
+    #[test]
+    fn test_check() {
+        let document = json!({
+            "version": "1.0",
+            "commands": [
+                { "name": " ", "description": "", "executable": "git", "args": "status",
+                  "aliases": ["g s"], "category": "tools" }
+            ],
+            "snippets": [{ "name": "", "text": "x" }],
+            "engines": [{ "keyword": "my search", "url": "https://x.test/?q={query}" }]
+        });
+
+        let problems = check(&document);
+        let messages: Vec<(&str, Option<&str>)> = problems
+            .iter()
+            .map(|p| (p.message.as_str(), p.key.as_deref()))
+            .collect();
+
+        assert_eq!(problems.len(), 5);
+        assert!(messages.contains(&("`snippets[0].name` is empty", Some("snippets[0].name"))));
+        assert!(messages.contains(&("command name is empty", Some("commands[0].name"))));
+        assert!(messages.contains(&("alias \"g s\" contains whitespace", Some("commands[0].aliases[0]"))));
+        assert!(messages.contains(&("search keyword \"my search\" must be a single word", Some("engines[0].keyword"))));
+        assert!(messages.iter().any(|(_, key)| *key == Some("commands[0].args")));
+    }
+
+    #[test]
+    fn test_published_schema_is_current() {
+        let published = include_str!("../commands.schema.json");
+        let current = serde_json::to_string_pretty(&config_schema()).unwrap();
+        assert_eq!(published.trim_end(), current, "run `--print-schema > commands.schema.json`");
+    }
+}
@@ -0,0 +1,109 @@
+// search.rs
+use crate::config::DEFAULT_CONFIG_FILES;
+use std::env;
+use std::path::{Path, PathBuf};
+
+// %ProgramData%\totalcontrol on Windows, /etc/totalcontrol elsewhere
+pub fn system_config_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        env::var_os("ProgramData").map(|dir| PathBuf::from(dir).join("totalcontrol"))
+    } else {
+        Some(PathBuf::from("/etc/totalcontrol"))
+    }
+}
+
+// %APPDATA%\totalcontrol on Windows, $XDG_CONFIG_HOME/totalcontrol elsewhere
+pub fn user_config_dir() -> Option<PathBuf> {
+    let dir = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+    };
+
+    dir.map(|dir| dir.join("totalcontrol"))
+}
+
+/// Configuration files to load, lowest priority first: the system-wide
+/// file, the user's file, then the nearest project file found by walking
+/// up from `cwd`.
+pub fn config_layers(cwd: &Path) -> Vec<PathBuf> {
+    config_layers_in(system_config_dir().as_deref(), user_config_dir().as_deref(), cwd)
+}
+
+pub fn config_layers_in(system_dir: Option<&Path>, user_dir: Option<&Path>, cwd: &Path) -> Vec<PathBuf> {
+    let mut layers: Vec<PathBuf> = [system_dir, user_dir]
+        .into_iter()
+        .flatten()
+        .filter_map(find_config)
+        .collect();
+
+    if let Some(project) = cwd.ancestors().find_map(find_config) {
+        if !layers.contains(&project) {
+            layers.push(project);
+        }
+    }
+
+    layers
+}
+
+/// Every file whose creation or change could alter [`config_layers`], so a
+/// watcher also notices configuration files that do not exist yet.
+pub fn candidate_paths(cwd: &Path) -> Vec<PathBuf> {
+    let system_dir = system_config_dir();
+    let user_dir = user_config_dir();
+
+    system_dir
+        .iter()
+        .chain(user_dir.iter())
+        .map(PathBuf::as_path)
+        .chain(cwd.ancestors())
+        .flat_map(|dir| DEFAULT_CONFIG_FILES.iter().map(move |name| dir.join(name)))
+        .collect()
+}
+
+// The first configuration file present in `dir`
+fn find_config(dir: &Path) -> Option<PathBuf> {
+    DEFAULT_CONFIG_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_config_layers_order() {
+        let root = TempDir::new().unwrap();
+        let system = root.path().join("system");
+        let user = root.path().join("user");
+        let project = root.path().join("project");
+        let nested = project.join("src").join("deep");
+        for dir in [&system, &user, &nested] {
+            fs::create_dir_all(dir).unwrap();
+        }
+
+        fs::write(system.join("commands.json"), "{}").unwrap();
+        fs::write(user.join("commands.toml"), "").unwrap();
+        fs::write(project.join("commands.yaml"), "").unwrap();
+
+        let layers = config_layers_in(Some(&system), Some(&user), &nested);
+        assert_eq!(
+            layers,
+            vec![
+                system.join("commands.json"),
+                user.join("commands.toml"),
+                project.join("commands.yaml"),
+            ]
+        );
+
+        // Missing layers are skipped
+        let layers = config_layers_in(None, Some(&root.path().join("nope")), &nested);
+        assert_eq!(layers, vec![project.join("commands.yaml")]);
+    }
+}
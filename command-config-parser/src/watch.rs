@@ -0,0 +1,153 @@
+// watch.rs
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Polls configuration files for changes by modification time.
+///
+/// A change is only reported once the files have stopped changing for the
+/// debounce period, so editors that write in several steps trigger a
+/// single reload.
+///
+/// Files that do not exist are not checked on every poll: only their
+/// directories are, and the files are looked for when one of those
+/// changes. A search of every ancestor directory thus costs one check per
+/// directory rather than one per candidate name.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    dirs: Vec<(PathBuf, Option<SystemTime>)>,
+    debounce: Duration,
+    pending_since: Option<Instant>,
+}
+
+impl ConfigWatcher {
+    pub fn new<I, P>(paths: I, debounce: Duration) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let mut watcher = Self {
+            files: Vec::new(),
+            dirs: Vec::new(),
+            debounce,
+            pending_since: None,
+        };
+        watcher.set_paths(paths);
+        watcher
+    }
+
+    // Replace the watched files, e.g. after a reload changed the includes
+    pub fn set_paths<I, P>(&mut self, paths: I)
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        self.files = paths
+            .into_iter()
+            .map(|path| {
+                let path = path.as_ref().to_path_buf();
+                let modified = modified(&path);
+                (path, modified)
+            })
+            .collect();
+        self.dirs.clear();
+        for dir in self.files.iter().filter_map(|(path, _)| path.parent()) {
+            if self.dirs.iter().all(|(known, _)| known != dir) {
+                self.dirs.push((dir.to_path_buf(), modified(dir)));
+            }
+        }
+        self.pending_since = None;
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(path, _)| path.as_path())
+    }
+
+    // Returns true once per settled change to any watched file
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        // Creating or removing a file changes its directory's time
+        let mut touched: Vec<&Path> = Vec::new();
+        for (dir, last) in &mut self.dirs {
+            let current = modified(dir);
+            if current != *last {
+                *last = current;
+                touched.push(dir);
+            }
+        }
+        for (path, last) in &mut self.files {
+            if last.is_none() && !path.parent().is_some_and(|dir| touched.contains(&dir)) {
+                continue;
+            }
+            let current = modified(path);
+            if current != *last {
+                *last = current;
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.pending_since = Some(Instant::now());
+        }
+
+        match self.pending_since {
+            Some(since) if since.elapsed() >= self.debounce => {
+                self.pending_since = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// Missing files report None, so creating or deleting one counts as a change
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tempfile::TempDir;
+
+    fn touch(path: &Path, secs: u64) {
+        let file = File::options().create(true).append(true).open(path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_poll_detects_changes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("commands.json");
+        touch(&path, 1_000);
+
+        let mut watcher = ConfigWatcher::new([&path], Duration::ZERO);
+        assert!(!watcher.poll());
+
+        touch(&path, 2_000);
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+
+        fs::remove_file(&path).unwrap();
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+
+        // A file that did not exist is noticed through its directory
+        fs::write(&path, "{}").unwrap();
+        assert!(watcher.poll());
+    }
+
+    #[test]
+    fn test_poll_debounces() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("commands.json");
+
+        let mut watcher = ConfigWatcher::new([&path], Duration::from_secs(3600));
+        touch(&path, 1_000);
+        assert!(!watcher.poll());
+        assert!(!watcher.poll());
+    }
+}
@@ -0,0 +1,794 @@
+use native_windows_gui as nwg;
+use totalcontrol_core::hotkey::key_for_virtual_key;
+use totalcontrol_core::{candidate_paths, Action, ChordMachine, ChordStep, CommandRegistry, ConfigError, ConfigWatcher, DangerLevel, Finished, History, HotkeyAction, HotkeyBinding, HotkeyManager, HotkeySpec, Invocation, Key, Launcher, Plan, Suggestion};
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::thread;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+const CONFIRM_RUN: &str = "Run";
+const CONFIRM_CANCEL: &str = "Cancel";
+
+#[derive(Default)]
+pub struct SearchBarApp {
+    window: nwg::Window,
+    input: nwg::TextInput,
+    listbox: nwg::ListBox<String>,
+    close_button: nwg::Button,
+    // The binding of each hotkey pressed
+    hotkey_receiver: Option<mpsc::Receiver<HotkeyBinding>>,
+    // Why a hotkey could not be registered, sent by the hotkey thread
+    hotkey_errors: Option<mpsc::Receiver<String>>,
+    poll_timer: nwg::AnimationTimer,
+    launcher: Launcher,
+    suggestions: RefCell<Vec<Suggestion>>,
+    config_watcher: Option<ConfigWatcher>,
+    config_error: Option<String>,
+    // A command waiting for parameter values typed into the input
+    pending: RefCell<Option<PendingRun>>,
+    prompt_options: RefCell<Vec<String>>,
+    // A resolved command waiting for the user to confirm it
+    confirming: RefCell<Option<Plan>>,
+    // Key sequences, waiting for the next key after a leader hotkey
+    chords: RefCell<ChordMachine>,
+    // What the keys held back by a sequence would have typed
+    chord_text: RefCell<String>,
+    // Failures and command output are reported through the tray and a
+    // separate output window
+    tray_icon: nwg::Icon,
+    tray: nwg::TrayNotification,
+    output_window: nwg::Window,
+    output_text: nwg::TextBox,
+}
+
+struct PendingRun {
+    query: String,
+    suggestion: Suggestion,
+    invocation: Invocation,
+    error: Option<String>,
+}
+
+impl SearchBarApp {
+    fn show_launcher(&self) {
+        println!("[DEBUG] Showing launcher window");
+        self.launcher.begin_session();
+        
+        // Clear previous input
+        self.input.set_text("");
+        
+        // Position window in center of screen
+        let screen_width = unsafe { 
+            windows::Win32::UI::WindowsAndMessaging::GetSystemMetrics(
+                windows::Win32::UI::WindowsAndMessaging::SM_CXSCREEN
+            ) 
+        };
+        let screen_height = unsafe { 
+            windows::Win32::UI::WindowsAndMessaging::GetSystemMetrics(
+                windows::Win32::UI::WindowsAndMessaging::SM_CYSCREEN
+            ) 
+        };
+        
+        let window_width = 500u32;
+        let window_height = 250u32;
+        let x = ((screen_width - window_width as i32) / 2) as i32;
+        let y = ((screen_height - window_height as i32) / 3) as i32; // Position in upper third
+        
+        self.window.set_position(x, y);
+        self.window.set_size(window_width, window_height);
+        
+        // Show and bring to front
+        self.window.set_visible(true);
+        
+        // Bring window to foreground
+        unsafe {
+            use windows::Win32::UI::WindowsAndMessaging::SetForegroundWindow;
+            use windows::Win32::Foundation::HWND;
+            
+            let hwnd = HWND(self.window.handle.hwnd().unwrap() as isize);
+            SetForegroundWindow(hwnd);
+        }
+        
+        // Focus the input field
+        self.input.set_focus();
+        
+        println!("[DEBUG] Launcher window should now be visible and focused");
+    }
+    
+    fn handle_hotkey(&self, action: &HotkeyAction) {
+        match self.launcher.hotkey_query(action) {
+            Some(query) => {
+                self.show_launcher();
+                self.input.set_text(&query);
+                let end = query.chars().count() as u32;
+                self.input.set_selection(end..end);
+            }
+            None => {
+                let HotkeyAction::Command(ref name) = *action else {
+                    return;
+                };
+                match self.launcher.command_suggestion(name) {
+                    Some(suggestion) => self.run(name, &suggestion, None),
+                    None => self.notify("Unknown command", &format!("No command named '{}'", name)),
+                }
+            }
+        }
+    }
+    
+    // After a leader hotkey, list the keys that can follow it in place of
+    // the results until the sequence ends
+    fn start_chord(&self, leader: &HotkeySpec) {
+        if !self.chords.borrow_mut().start(leader, Instant::now()) {
+            return;
+        }
+        self.chord_text.borrow_mut().clear();
+        println!("[DEBUG] Waiting for a key sequence after {}", leader);
+        self.listbox.clear();
+        self.suggestions.borrow_mut().clear();
+        self.show_chord_hints();
+    }
+    
+    fn show_chord_hints(&self) {
+        for (i, hint) in self.chords.borrow().hints().iter().enumerate() {
+            self.listbox.insert(i, hint.to_string());
+        }
+    }
+    
+    // Feed a key press to the sequence in progress; true when it was used
+    // up and must not reach the input
+    fn press_chord_key(&self, key_code: u32) -> bool {
+        if !self.chords.borrow().is_pending() {
+            return false;
+        }
+        // Modifiers and keys hotkeys cannot name leave the sequence alone
+        let Some(key) = key_for_virtual_key(key_code) else {
+            return false;
+        };
+        
+        let step = self.chords.borrow_mut().press(key, Instant::now());
+        match step {
+            ChordStep::Pending => {
+                self.chord_text.borrow_mut().push_str(&typed_text(key_code));
+                self.listbox.clear();
+                self.show_chord_hints();
+                true
+            }
+            ChordStep::Matched(action) => {
+                println!("[DEBUG] Key sequence matched: {:?}", action);
+                self.chord_text.borrow_mut().clear();
+                self.handle_hotkey(&action);
+                true
+            }
+            ChordStep::NoMatch(mut keys) => {
+                // This key goes on to the input as usual
+                keys.pop();
+                self.abandon_chord(&keys);
+                false
+            }
+        }
+    }
+    
+    // Type the keys held back by a sequence that went nowhere, then search
+    fn abandon_chord(&self, keys: &[Key]) {
+        println!("[DEBUG] Key sequence abandoned after {} keys", keys.len());
+        let text = self.input.text() + &self.chord_text.take();
+        self.input.set_text(&text);
+        let end = text.chars().count() as u32;
+        self.input.set_selection(end..end);
+        self.handle_input_change();
+    }
+    
+    fn hide_launcher(&self) {
+        println!("[DEBUG] Hiding launcher window");
+        self.pending.borrow_mut().take();
+        self.confirming.borrow_mut().take();
+        self.chords.borrow_mut().cancel();
+        self.chord_text.borrow_mut().clear();
+        self.window.set_visible(false);
+    }
+    
+    fn handle_input_change(&self) {
+        let query = self.input.text();
+        println!("[DEBUG] Input changed: '{}'", query);
+        
+        // Clear existing suggestions
+        self.listbox.clear();
+        self.suggestions.borrow_mut().clear();
+        
+        if self.pending.borrow().is_some() {
+            self.show_prompt(&query);
+            return;
+        }
+        
+        if self.confirming.borrow().is_some() {
+            self.show_confirmation();
+            return;
+        }
+        
+        if self.chords.borrow().is_pending() {
+            self.show_chord_hints();
+            return;
+        }
+        
+        if query.is_empty() {
+            // Show default suggestions
+            self.listbox.insert(0, "Type to search...".to_string());
+            self.show_config_error();
+            return;
+        }
+        
+        let suggestions = self.launcher.suggest(&query);
+        for (i, suggestion) in suggestions.iter().enumerate() {
+            self.listbox.insert(i, suggestion.to_string());
+        }
+        
+        if suggestions.is_empty() {
+            self.listbox.insert(0, format!("No results for '{}'", query));
+        }
+        
+        *self.suggestions.borrow_mut() = suggestions;
+        self.show_config_error();
+    }
+    
+    // The parameter being asked for, with matching choices below it
+    fn show_prompt(&self, typed: &str) {
+        let pending = self.pending.borrow();
+        let Some(ref run) = *pending else {
+            return;
+        };
+        let Some(param) = run.invocation.current() else {
+            return;
+        };
+        
+        let mut header = format!("{} › {}", run.invocation.command(), param.label());
+        if let Some(ref default) = param.default {
+            header.push_str(&format!(" (default: {})", default));
+        }
+        if let Some(ref error) = run.error {
+            header.push_str(&format!(" — {}", error));
+        }
+        self.listbox.insert(0, header);
+        
+        let options: Vec<String> = run.invocation.options(typed).into_iter().map(str::to_string).collect();
+        for (i, option) in options.iter().enumerate() {
+            self.listbox.insert(i + 1, option.clone());
+        }
+        *self.prompt_options.borrow_mut() = options;
+    }
+    
+    fn answer_prompt(&self) {
+        let typed = self.input.text();
+        let options = self.prompt_options.borrow().clone();
+        
+        // A highlighted choice wins, then what was typed, then the best choice
+        let value = match self.listbox.selection() {
+            Some(index) if index > 0 && index <= options.len() => options[index - 1].clone(),
+            _ if typed.trim().is_empty() && !options.is_empty() => options[0].clone(),
+            _ => typed,
+        };
+        
+        let mut pending = self.pending.borrow_mut();
+        let Some(ref mut run) = *pending else {
+            return;
+        };
+        
+        if let Err(err) = run.invocation.answer(&value) {
+            run.error = Some(err);
+        } else if run.invocation.is_complete() {
+            let run = pending.take().unwrap();
+            drop(pending);
+            self.run(&run.query, &run.suggestion, Some(&run.invocation));
+            return;
+        } else {
+            run.error = None;
+        }
+        
+        drop(pending);
+        self.input.set_text("");
+        self.listbox.clear();
+        self.show_prompt("");
+    }
+    
+    fn run(&self, query: &str, suggestion: &Suggestion, invocation: Option<&Invocation>) {
+        // "explain ..." shows how the rest resolves instead of running it
+        if let Action::Explain(ref explained) = suggestion.action {
+            let explanation = self.launcher.explain(explained);
+            println!("[DEBUG] {}", explanation);
+            self.show_output(&format!("TotalControl - explain {}", explained), &explanation.to_string());
+            self.hide_launcher();
+            return;
+        }
+        
+        let plan = match self.launcher.plan(query, suggestion, invocation) {
+            Ok(plan) => plan,
+            Err(err) => {
+                eprintln!("[ERROR] Failed to execute '{}': {}", suggestion.title, err);
+                self.notify("Could not run command", &err.to_string());
+                self.hide_launcher();
+                return;
+            }
+        };
+        
+        // Risky commands wait for an explicit go-ahead
+        if plan.confirmation.is_some() {
+            println!("[DEBUG] Asking for confirmation: {:?}", plan.action);
+            // Run from a hotkey, so the launcher is not open yet
+            if !self.window.visible() {
+                self.show_launcher();
+            }
+            *self.confirming.borrow_mut() = Some(plan);
+            self.input.set_text("");
+            self.listbox.clear();
+            self.show_confirmation();
+            return;
+        }
+        
+        self.launch(&plan);
+    }
+    
+    fn launch(&self, plan: &Plan) {
+        println!("[DEBUG] Executing action: {:?}", plan.action);
+        
+        if let Err(err) = self.launcher.run(plan) {
+            eprintln!("[ERROR] Failed to execute '{}': {}", plan.query, err);
+            self.notify("Could not run command", &err.to_string());
+        }
+        
+        self.hide_launcher();
+    }
+    
+    // The full command line and why it is risky, then Run / Cancel. Enter
+    // on a high danger command cancels unless Run is picked.
+    fn show_confirmation(&self) {
+        let confirming = self.confirming.borrow();
+        let Some(confirmation) = confirming.as_ref().and_then(|plan| plan.confirmation.as_ref()) else {
+            return;
+        };
+        
+        let mut rows = vec![format!("⚠ Run this command? ({:?} danger)", confirmation.level)];
+        rows.extend(confirmation.reasons.iter().map(|reason| format!("  • {}", reason)));
+        rows.push(format!("  {}", confirmation.command_line));
+        rows.push(CONFIRM_RUN.to_string());
+        rows.push(CONFIRM_CANCEL.to_string());
+        
+        for (i, row) in rows.iter().enumerate() {
+            self.listbox.insert(i, row.clone());
+        }
+        let preselected = if confirmation.level == DangerLevel::High { rows.len() - 1 } else { rows.len() - 2 };
+        self.listbox.set_selection(Some(preselected));
+    }
+    
+    fn answer_confirmation(&self) {
+        let Some(plan) = self.confirming.borrow_mut().take() else {
+            return;
+        };
+        let choice = self.listbox.selection().and_then(|index| self.listbox.collection().get(index).cloned());
+        
+        if choice.as_deref() == Some(CONFIRM_RUN) {
+            self.launch(&plan);
+        } else {
+            println!("[DEBUG] Cancelled {:?}", plan.action);
+            self.hide_launcher();
+        }
+    }
+    
+    fn notify(&self, title: &str, text: &str) {
+        let flags = nwg::TrayNotificationFlags::ERROR_ICON;
+        self.tray.show(text, Some(title), Some(flags), None);
+    }
+    
+    fn report(&self, finished: &Finished) {
+        let outcome = &finished.outcome;
+        println!("[DEBUG] {} (pid {})", outcome.summary(), outcome.pid);
+        
+        if !finished.should_report() {
+            return;
+        }
+        if !outcome.success {
+            self.notify("Command failed", &outcome.summary());
+        }
+        
+        let mut text = outcome.summary();
+        if !outcome.stdout.is_empty() {
+            text.push_str(&format!("\n\n{}", outcome.stdout));
+        }
+        if !outcome.stderr.is_empty() {
+            text.push_str(&format!("\n\n--- stderr ---\n{}", outcome.stderr));
+        }
+        self.show_output(&format!("TotalControl - {}", outcome.label), &text);
+    }
+    
+    fn show_output(&self, title: &str, text: &str) {
+        self.output_window.set_text(title);
+        self.output_text.set_text_unix2dos(text);
+        self.output_window.set_visible(true);
+    }
+    
+    // Broken config is reported below the results, never blocking them
+    fn show_config_error(&self) {
+        if let Some(ref err) = self.config_error {
+            self.listbox.insert(self.listbox.len(), format!("⚠ Command configuration: {}", err));
+        }
+    }
+    
+    fn reload_commands(&mut self) {
+        println!("[DEBUG] Command configuration changed, reloading");
+        
+        let reloaded = match self.launcher.registry() {
+            Some(registry) => registry.reload(),
+            None => CommandRegistry::load_from_default(),
+        };
+        
+        match reloaded {
+            Ok(registry) => {
+                println!("[DEBUG] Reloaded {} commands", registry.get_all_commands().len());
+                for warning in registry.warnings() {
+                    eprintln!("[WARN] {}", warning);
+                }
+                let bindings = registry.hotkey_bindings();
+                if self.launcher.registry().is_some_and(|current| current.hotkey_bindings() != bindings) {
+                    println!("[DEBUG] Hotkeys changed; they take effect after a restart");
+                }
+                if let Some(ref mut watcher) = self.config_watcher {
+                    watcher.set_paths(registry.watch_paths());
+                }
+                *self.chords.borrow_mut() = ChordMachine::new(registry.sequences().to_vec(), registry.sequence_timeout());
+                self.launcher.set_registry(registry);
+                self.config_error = None;
+            }
+            Err(err) => {
+                // Keep the previous commands live
+                eprint!("[ERROR] Failed to reload command configuration:\n{}", err.render());
+                self.config_error = Some(err.to_string());
+            }
+        }
+        
+        self.handle_input_change();
+    }
+    
+    fn execute_command(&self) {
+        if self.pending.borrow().is_some() {
+            self.answer_prompt();
+            return;
+        }
+        
+        if self.confirming.borrow().is_some() {
+            self.answer_confirmation();
+            return;
+        }
+        
+        let selected_index = self.listbox.selection();
+        if let Some(index) = selected_index {
+            // Placeholder rows have no suggestion behind them
+            let Some(suggestion) = self.suggestions.borrow().get(index).cloned() else {
+                return;
+            };
+            let query = self.input.text();
+            
+            match self.launcher.prepare(&query, &suggestion) {
+                // Ask for the missing parameters inline before running
+                Some(invocation) if !invocation.is_complete() => {
+                    *self.pending.borrow_mut() = Some(PendingRun {
+                        query,
+                        suggestion,
+                        invocation,
+                        error: None,
+                    });
+                    self.input.set_text("");
+                    self.listbox.clear();
+                    self.show_prompt("");
+                }
+                invocation => self.run(&query, &suggestion, invocation.as_ref()),
+            }
+        }
+    }
+}
+
+// Event handling structure
+#[derive(Default)]
+struct AppEvents {
+    app: Option<Rc<RefCell<SearchBarApp>>>,
+    last_input_text: String,
+}
+
+impl AppEvents {
+    fn new(app: Rc<RefCell<SearchBarApp>>) -> Self {
+        Self {
+            app: Some(app),
+            last_input_text: String::new(),
+        }
+    }
+    
+    fn handle_timer(&mut self) {
+        if let Some(ref app) = self.app {
+            let mut app_ref = app.borrow_mut();
+            
+            // Pick up edits to the command configuration
+            if app_ref.config_watcher.as_mut().is_some_and(|watcher| watcher.poll()) {
+                app_ref.reload_commands();
+            }
+            
+            for finished in app_ref.launcher.poll_finished() {
+                app_ref.report(&finished);
+            }
+            
+            for problem in app_ref.launcher.take_problems() {
+                eprintln!("[ERROR] {}", problem);
+            }
+            
+            if let Some(err) = app_ref.hotkey_errors.as_ref().and_then(|errors| errors.try_recv().ok()) {
+                eprintln!("[ERROR] {}", err);
+                app_ref.notify("Hotkey unavailable", &err);
+            }
+            
+            // A sequence whose next key is late types what it held back
+            let expired = app_ref.chords.borrow_mut().expire(Instant::now());
+            if let Some(keys) = expired {
+                app_ref.abandon_chord(&keys);
+            }
+            
+            if let Some(ref receiver) = app_ref.hotkey_receiver {
+                if let Ok(binding) = receiver.try_recv() {
+                    println!("[DEBUG] Received hotkey signal: {}", binding.keys);
+                    drop(app_ref); // Release the mutable borrow
+                    let app_ref = app.borrow();
+                    app_ref.handle_hotkey(&binding.action);
+                    app_ref.start_chord(&binding.keys);
+                }
+            }
+        }
+    }
+    
+    fn handle_input_change(&mut self) {
+        if let Some(ref app) = self.app {
+            let current_text = app.borrow().input.text();
+            if current_text != self.last_input_text {
+                self.last_input_text = current_text;
+                app.borrow().handle_input_change();
+            }
+        }
+    }
+    
+    fn handle_button_click(&self) {
+        if let Some(ref app) = self.app {
+            app.borrow().hide_launcher();
+        }
+    }
+    
+    fn handle_listbox_double_click(&self) {
+        if let Some(ref app) = self.app {
+            app.borrow().execute_command();
+        }
+    }
+    
+    fn handle_window_close(&self) {
+        if let Some(ref app) = self.app {
+            app.borrow().hide_launcher();
+        }
+        nwg::stop_thread_dispatch();
+    }
+    
+    // True when the key was used up by a key sequence
+    fn handle_key_press(&self, key_code: u32) -> bool {
+        if let Some(ref app) = self.app {
+            if app.borrow().press_chord_key(key_code) {
+                return true;
+            }
+        }
+        
+        if key_code == 13 { // Enter key
+            if let Some(ref app) = self.app {
+                app.borrow().execute_command();
+            }
+        } else if key_code == 27 { // Escape key
+            if let Some(ref app) = self.app {
+                app.borrow().hide_launcher();
+            }
+        }
+        false
+    }
+}
+
+// The text a key press types with the layout and modifiers held right now,
+// so keys held back by a sequence keep their Shift and Caps Lock
+fn typed_text(key_code: u32) -> String {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetKeyboardState, MapVirtualKeyW, ToUnicode, MAPVK_VK_TO_VSC};
+    
+    let mut state = [0u8; 256];
+    let mut buffer = [0u16; 8];
+    let written = unsafe {
+        if !GetKeyboardState(&mut state).as_bool() {
+            return String::new();
+        }
+        let scan_code = MapVirtualKeyW(key_code, MAPVK_VK_TO_VSC);
+        // 4 leaves a pending dead key alone
+        ToUnicode(key_code, scan_code, Some(&state), &mut buffer, 4)
+    };
+    String::from_utf16_lossy(&buffer[..written.max(0) as usize])
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect()
+}
+
+pub fn run() {
+    
+    nwg::init().expect("Failed to init Native Windows GUI");
+    
+    // Create font for better appearance
+    let mut font = nwg::Font::default();
+    nwg::Font::builder()
+        .family("Segoe UI")
+        .size(16)
+        .build(&mut font)
+        .expect("Failed to create font");
+    
+    let mut app = SearchBarApp::default();
+    
+    // Load user-defined commands
+    let mut watched = std::env::current_dir().map(|cwd| candidate_paths(&cwd)).unwrap_or_default();
+    match CommandRegistry::load_from_default() {
+        Ok(registry) => {
+            println!("[DEBUG] Loaded {} commands from {:?}", registry.get_all_commands().len(), registry.sources());
+            for warning in registry.warnings() {
+                eprintln!("[WARN] {}", warning);
+            }
+            watched = registry.watch_paths();
+            app.chords = RefCell::new(ChordMachine::new(registry.sequences().to_vec(), registry.sequence_timeout()));
+            app.launcher.set_registry(registry);
+        }
+        Err(ConfigError::FileNotFound) => println!("[DEBUG] No command configuration found, using built-in suggestions"),
+        Err(err) => {
+            eprint!("[ERROR] Failed to load command configuration:\n{}", err.render());
+            app.config_error = Some(err.to_string());
+        }
+    }
+    
+    // Watch for edits so commands can be reloaded without a restart
+    app.config_watcher = Some(ConfigWatcher::new(watched, Duration::from_millis(300)));
+    
+    // Load launch history for frecency ranking
+    match History::load_from_default() {
+        Ok(history) => app.launcher.set_history(history),
+        Err(err) => eprintln!("[ERROR] Failed to load launch history: {}", err),
+    }
+    
+    // Create the main window (initially hidden)
+    nwg::Window::builder()
+        .size((500, 250))
+        .position((300, 300))
+        .title("TotalControl")
+        .flags(nwg::WindowFlags::WINDOW | nwg::WindowFlags::VISIBLE)
+        .build(&mut app.window)
+        .expect("Failed to create window");
+    
+    // Hide initially
+    app.window.set_visible(false);
+    
+    // Create search input
+    nwg::TextInput::builder()
+        .parent(&app.window)
+        .size((460, 35))
+        .position((20, 20))
+        .placeholder_text(Some("Type to search..."))
+        .font(Some(&font))
+        .build(&mut app.input)
+        .expect("Failed to create input");
+    
+    // Create suggestions listbox
+    nwg::ListBox::builder()
+        .parent(&app.window)
+        .size((460, 150))
+        .position((20, 65))
+        .font(Some(&font))
+        .build(&mut app.listbox)
+        .expect("Failed to create listbox");
+    
+    // Add default suggestion
+    app.listbox.insert(0, "Press Ctrl+Space to activate".to_string());
+    
+    // Create close button
+    nwg::Button::builder()
+        .parent(&app.window)
+        .size((80, 25))
+        .position((400, 220))
+        .text("Close")
+        .font(Some(&font))
+        .build(&mut app.close_button)
+        .expect("Failed to create button");
+    
+    // Tray icon used for failure notifications
+    app.tray_icon = nwg::Icon::from_system(nwg::OemIcon::Information);
+    nwg::TrayNotification::builder()
+        .parent(&app.window)
+        .icon(Some(&app.tray_icon))
+        .tip(Some("TotalControl"))
+        .build(&mut app.tray)
+        .expect("Failed to create tray notification");
+    
+    // Output window for failed commands and commands with show_output
+    nwg::Window::builder()
+        .size((600, 400))
+        .position((320, 320))
+        .title("TotalControl - Output")
+        .flags(nwg::WindowFlags::WINDOW | nwg::WindowFlags::RESIZABLE)
+        .build(&mut app.output_window)
+        .expect("Failed to create output window");
+    
+    nwg::TextBox::builder()
+        .parent(&app.output_window)
+        .size((580, 380))
+        .position((10, 10))
+        .readonly(true)
+        .font(Some(&font))
+        .build(&mut app.output_text)
+        .expect("Failed to create output text box");
+    
+    // Create animation timer for polling hotkey events
+    nwg::AnimationTimer::builder()
+        .parent(&app.window)
+        .interval(std::time::Duration::from_millis(50))
+        .build(&mut app.poll_timer)
+        .expect("Failed to create timer");
+    
+    // Set up hotkey monitoring in separate thread
+    let (tx, rx) = mpsc::channel();
+    app.hotkey_receiver = Some(rx);
+    let (error_tx, error_rx) = mpsc::channel();
+    app.hotkey_errors = Some(error_rx);
+    let bindings = match app.launcher.registry() {
+        Some(registry) => registry.hotkey_bindings(),
+        None => vec![HotkeyBinding { keys: Default::default(), action: HotkeyAction::Launcher }],
+    };
+    
+    thread::spawn(move || match totalcontrol_core::select_backend() {
+        Ok(backend) => {
+            println!("[DEBUG] Listening for {} hotkeys with {}", bindings.len(), backend.name());
+            HotkeyManager::new(backend, bindings).run(tx, error_tx);
+        }
+        Err(err) => {
+            error_tx.send(err).ok();
+        }
+    });
+    
+    let app_rc = Rc::new(RefCell::new(app));
+    let mut events = AppEvents::new(app_rc.clone());
+    
+    // Start the timer
+    app_rc.borrow().poll_timer.start();
+    
+    // Manual event loop using NWG's message dispatch
+    let ui = nwg::dispatch_thread_events_with_callback(move || {
+        // Check for timer events
+        events.handle_timer();
+        
+        // Check for input changes
+        events.handle_input_change();
+        
+        // Handle window messages manually
+        unsafe {
+            use windows::Win32::UI::WindowsAndMessaging::{PeekMessageA, MSG, PM_REMOVE, WM_KEYDOWN, WM_LBUTTONDBLCLK, WM_COMMAND};
+            use windows::Win32::Foundation::HWND;
+            
+            let mut msg = MSG::default();
+            while PeekMessageA(&mut msg, HWND(0), 0, 0, PM_REMOVE).as_bool() {
+                match msg.message {
+                    WM_KEYDOWN if events.handle_key_press(msg.wParam.0 as u32) => continue,
+                    WM_LBUTTONDBLCLK => {
+                        // Check if it's from the listbox
+                        events.handle_listbox_double_click();
+                    }
+                    WM_COMMAND => {
+                        // Check if it's from the button
+                        events.handle_button_click();
+                    }
+                    _ => {}
+                }
+                
+                windows::Win32::UI::WindowsAndMessaging::TranslateMessage(&msg);
+                windows::Win32::UI::WindowsAndMessaging::DispatchMessageA(&msg);
+            }
+        }
+    });
+    
+    println!("[DEBUG] UI built, starting message loop. Press Ctrl+Space to activate!");
+}
\ No newline at end of file
@@ -0,0 +1,30 @@
+// action.rs
+/// What the launcher will do once a suggestion is picked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    OpenUrl(String),
+    LaunchExecutable(String),
+    ShellCommand(String),
+    // A user-defined command, looked up by name in the command registry
+    RegistryCommand(String),
+    CopyText(String),
+    // Stop a process the launcher started, by PID
+    CancelProcess(u32),
+    // Show how a query resolves without running anything
+    Explain(String),
+}
+
+impl Action {
+    // Stable identity of the launched item, used by the launch history
+    pub fn key(&self) -> String {
+        match self {
+            Action::OpenUrl(url) => format!("url:{}", url),
+            Action::LaunchExecutable(path) => format!("exe:{}", path),
+            Action::ShellCommand(command) => format!("shell:{}", command),
+            Action::RegistryCommand(name) => format!("command:{}", name),
+            Action::CopyText(text) => format!("copy:{}", text),
+            Action::CancelProcess(pid) => format!("cancel:{}", pid),
+            Action::Explain(query) => format!("explain:{}", query),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+// chord.rs
+use command_config_parser::{HotkeyAction, HotkeySpec, Key, SequenceBinding};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// What a key pressed during a sequence did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChordStep {
+    /// The keys so far start a sequence; the next one is awaited.
+    Pending,
+    /// A sequence is complete.
+    Matched(HotkeyAction),
+    /// No sequence goes on this way. Carries every key pressed since the
+    /// leader, this one included, so they can be typed as usual.
+    NoMatch(Vec<Key>),
+}
+
+/// A key that continues the sequence in progress, for the hint overlay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChordHint {
+    pub key: Key,
+    // What pressing it does, when it completes a sequence
+    pub action: Option<HotkeyAction>,
+    // Sequences still reachable after pressing it
+    pub sequences: usize,
+}
+
+struct Pending {
+    leader: HotkeySpec,
+    keys: Vec<Key>,
+    deadline: Instant,
+}
+
+/// Follows key sequences such as `Ctrl+Space g s`: the leader hotkey
+/// starts one, then each key has to come within the timeout of the last.
+#[derive(Default)]
+pub struct ChordMachine {
+    sequences: Vec<SequenceBinding>,
+    timeout: Duration,
+    pending: Option<Pending>,
+}
+
+impl ChordMachine {
+    pub fn new(sequences: Vec<SequenceBinding>, timeout: Duration) -> Self {
+        Self {
+            sequences,
+            timeout,
+            pending: None,
+        }
+    }
+
+    // Wait for the keys after `leader`; false when no sequence starts with it
+    pub fn start(&mut self, leader: &HotkeySpec, now: Instant) -> bool {
+        if self.sequences.iter().all(|binding| binding.keys.leader != *leader) {
+            return false;
+        }
+        self.pending = Some(Pending {
+            leader: *leader,
+            keys: Vec::new(),
+            deadline: now + self.timeout,
+        });
+        true
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn press(&mut self, key: Key, now: Instant) -> ChordStep {
+        let Some(mut pending) = self.pending.take() else {
+            return ChordStep::NoMatch(vec![key]);
+        };
+        pending.keys.push(key);
+        if now > pending.deadline {
+            return ChordStep::NoMatch(pending.keys);
+        }
+
+        let mut continuing = self
+            .sequences
+            .iter()
+            .filter(|binding| binding.keys.leader == pending.leader && binding.keys.keys.starts_with(&pending.keys));
+        match continuing.next() {
+            None => ChordStep::NoMatch(pending.keys),
+            // Sequences never start with another, so a complete one is alone
+            Some(binding) if binding.keys.keys == pending.keys => ChordStep::Matched(binding.action.clone()),
+            Some(_) => {
+                pending.deadline = now + self.timeout;
+                self.pending = Some(pending);
+                ChordStep::Pending
+            }
+        }
+    }
+
+    // Give up on a sequence whose next key is late; returns the keys
+    // pressed since the leader
+    pub fn expire(&mut self, now: Instant) -> Option<Vec<Key>> {
+        if self.pending.as_ref().is_some_and(|pending| now > pending.deadline) {
+            return self.pending.take().map(|pending| pending.keys);
+        }
+        None
+    }
+
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+
+    // The keys that can come next, in configuration order
+    pub fn hints(&self) -> Vec<ChordHint> {
+        let Some(ref pending) = self.pending else {
+            return Vec::new();
+        };
+        let depth = pending.keys.len();
+
+        let mut hints: Vec<ChordHint> = Vec::new();
+        for binding in &self.sequences {
+            let keys = &binding.keys.keys;
+            if binding.keys.leader != pending.leader || !keys.starts_with(&pending.keys) {
+                continue;
+            }
+            let Some(&key) = keys.get(depth) else {
+                continue;
+            };
+            let action = (keys.len() == depth + 1).then(|| binding.action.clone());
+            match hints.iter_mut().find(|hint| hint.key == key) {
+                Some(hint) => hint.sequences += 1,
+                None => hints.push(ChordHint { key, action, sequences: 1 }),
+            }
+        }
+        hints
+    }
+}
+
+impl fmt::Display for ChordHint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.key {
+            Key::Char(c) => write!(f, "{}", c.to_ascii_lowercase())?,
+            key => write!(f, "{}", key)?,
+        }
+        match self.action {
+            Some(HotkeyAction::Launcher) => write!(f, "  open the launcher"),
+            Some(HotkeyAction::Category(ref category)) => write!(f, "  @{}", category),
+            Some(HotkeyAction::Command(ref name)) => write!(f, "  {}", name),
+            Some(HotkeyAction::Clipboard) => write!(f, "  clipboard"),
+            None if self.sequences == 1 => write!(f, "  …"),
+            None => write!(f, "  … ({} sequences)", self.sequences),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(keys: &str, command: &str) -> SequenceBinding {
+        SequenceBinding {
+            keys: keys.parse().unwrap(),
+            action: HotkeyAction::Command(command.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_sequences() {
+        let mut chords = ChordMachine::new(
+            vec![
+                sequence("Ctrl+Space g s", "git-status"),
+                sequence("Ctrl+Space g p", "git-pull"),
+                sequence("Ctrl+Space d", "deploy"),
+            ],
+            Duration::from_millis(500),
+        );
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        assert!(!chords.start(&"Super+Space".parse().unwrap(), start));
+        assert!(chords.start(&HotkeySpec::default(), start));
+        let hints: Vec<String> = chords.hints().iter().map(ToString::to_string).collect();
+        assert_eq!(hints, ["g  … (2 sequences)", "d  deploy"]);
+
+        assert_eq!(chords.press(Key::Char('G'), at(400)), ChordStep::Pending);
+        assert_eq!(chords.hints().len(), 2);
+        // The timeout counts from the last key, not the leader
+        assert_eq!(
+            chords.press(Key::Char('P'), at(800)),
+            ChordStep::Matched(HotkeyAction::Command("git-pull".to_string()))
+        );
+        assert!(!chords.is_pending());
+
+        chords.start(&HotkeySpec::default(), start);
+        assert_eq!(chords.press(Key::Char('G'), at(100)), ChordStep::Pending);
+        assert_eq!(chords.press(Key::Char('X'), at(200)), ChordStep::NoMatch(vec![Key::Char('G'), Key::Char('X')]));
+
+        chords.start(&HotkeySpec::default(), start);
+        assert_eq!(chords.expire(at(300)), None);
+        assert_eq!(chords.expire(at(600)), Some(Vec::new()));
+        assert_eq!(chords.press(Key::Char('D'), at(700)), ChordStep::NoMatch(vec![Key::Char('D')]));
+    }
+}
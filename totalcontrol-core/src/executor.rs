@@ -0,0 +1,221 @@
+// executor.rs
+use crate::action::Action;
+use crate::process::ProcessHandle;
+use command_config_parser::Command as RegistryCommand;
+use command_config_parser::{system_vars, EnvMode, ExecMode};
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Carries out a resolved [`Action`]. Anything that starts a process
+/// returns its handle so the launcher can report how it ended.
+pub trait Executor {
+    fn execute(&self, action: &Action) -> io::Result<Option<ProcessHandle>>;
+
+    // Registry commands are resolved by the launcher before reaching here
+    fn run_command(&self, command: &RegistryCommand) -> io::Result<Option<ProcessHandle>>;
+
+    // For `$VAR`, `%VAR%` and `~` in command fields
+    fn env_var(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+
+    // For the `{clipboard}` and `{selection}` placeholders
+    fn read_clipboard(&self) -> io::Result<String> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "no clipboard available"))
+    }
+
+    fn read_selection(&self) -> io::Result<String> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "no selection available"))
+    }
+}
+
+/// The variables a registry command's process starts with, after its
+/// `env_mode`. Unless `inherit` is set they are its whole environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandEnv {
+    pub inherit: bool,
+    pub vars: Vec<(String, String)>,
+}
+
+impl CommandEnv {
+    // For an expanded command; `lookup` reads the launcher's environment
+    pub fn new(command: &RegistryCommand, lookup: &impl Fn(&str) -> Option<String>) -> Self {
+        let inherit = command.env_mode != EnvMode::Replace;
+        let mut vars = if inherit { Vec::new() } else { system_vars(lookup) };
+        let mut own: Vec<(String, String)> = command.env_vars.iter().flatten().map(|(n, v)| (n.clone(), v.clone())).collect();
+        own.sort();
+        for (name, value) in own {
+            vars.retain(|(known, _)| *known != name);
+            vars.push((name, value));
+        }
+        Self { inherit, vars }
+    }
+}
+
+/// Spawns actions as child processes of the current OS, capturing their
+/// output.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemExecutor;
+
+impl Executor for SystemExecutor {
+    fn execute(&self, action: &Action) -> io::Result<Option<ProcessHandle>> {
+        let handle = match action {
+            Action::OpenUrl(url) => return open_url(url),
+            Action::LaunchExecutable(path) => ProcessHandle::spawn(path.as_str(), Command::new(path))?,
+            // Typed by the user as a command line, so meant for the shell
+            Action::ShellCommand(command) => {
+                ProcessHandle::spawn(command.as_str(), shell(ExecMode::platform_shell(), command)?)?
+            }
+            Action::CopyText(text) => return copy_to_clipboard(text).map(|_| None),
+            Action::RegistryCommand(name) => return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("no command registry to run '{}'", name),
+            )),
+            Action::CancelProcess(pid) => return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("process {} was not started by this executor", pid),
+            )),
+            Action::Explain(query) => return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("explaining '{}' is up to the frontend", query),
+            )),
+        };
+        Ok(Some(handle))
+    }
+
+    fn run_command(&self, command: &RegistryCommand) -> io::Result<Option<ProcessHandle>> {
+        let mut cmd = if command.exec.is_direct() {
+            let mut cmd = Command::new(&command.executable);
+            cmd.args(&command.args);
+            cmd
+        } else {
+            shell(command.exec, &command.exec.script(&command.executable, &command.args))?
+        };
+
+        if let Some(ref dir) = command.working_dir {
+            cmd.current_dir(dir);
+        }
+        let env = CommandEnv::new(command, &|name| self.env_var(name));
+        if !env.inherit {
+            cmd.env_clear();
+        }
+        cmd.envs(env.vars);
+
+        let mut handle = ProcessHandle::spawn(command.name.as_str(), cmd)?.with_kill_signal(command.kill_signal);
+        if let Some(secs) = command.timeout {
+            handle = handle.with_timeout(Duration::from_secs(secs));
+        }
+        Ok(Some(handle))
+    }
+
+    fn read_clipboard(&self) -> io::Result<String> {
+        read_output(paste_command(false))
+    }
+
+    #[cfg(not(windows))]
+    fn read_selection(&self) -> io::Result<String> {
+        read_output(paste_command(true))
+    }
+}
+
+// Handed to the shell's URL handler directly; `cmd /C start` would read
+// `&` in a query string as a command separator
+#[cfg(windows)]
+fn open_url(url: &str) -> io::Result<Option<ProcessHandle>> {
+    use windows::core::{w, HSTRING, PCWSTR};
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    // SAFETY: every string outlives the call
+    let result = unsafe { ShellExecuteW(HWND(0), w!("open"), &HSTRING::from(url), PCWSTR::null(), PCWSTR::null(), SW_SHOWNORMAL) };
+    // Values up to 32 are error codes
+    if result.0 <= 32 {
+        return Err(io::Error::other(format!("failed to open {} (error {})", url, result.0)));
+    }
+    Ok(None)
+}
+
+#[cfg(not(windows))]
+fn open_url(url: &str) -> io::Result<Option<ProcessHandle>> {
+    let mut cmd = Command::new("xdg-open");
+    cmd.arg(url);
+    ProcessHandle::spawn(url, cmd).map(Some)
+}
+
+fn shell(mode: ExecMode, script: &str) -> io::Result<Command> {
+    let Some((program, flags)) = mode.shell() else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "direct commands have no shell to run a script in"));
+    };
+    let mut cmd = Command::new(program);
+    cmd.args(flags);
+
+    // cmd.exe has its own quoting rules; pass the script as it is, with
+    // the outer quotes `/S` strips
+    #[cfg(windows)]
+    if mode == ExecMode::Cmd {
+        std::os::windows::process::CommandExt::raw_arg(&mut cmd, format!("\"{}\"", script));
+        return Ok(cmd);
+    }
+
+    cmd.arg(script);
+    Ok(cmd)
+}
+
+fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let mut child = clipboard_command().stdin(Stdio::piped()).spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    child.wait().map(drop)
+}
+
+#[cfg(windows)]
+fn clipboard_command() -> Command {
+    Command::new("clip")
+}
+
+#[cfg(not(windows))]
+fn clipboard_command() -> Command {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        Command::new("wl-copy")
+    } else {
+        let mut cmd = Command::new("xclip");
+        cmd.args(["-selection", "clipboard"]);
+        cmd
+    }
+}
+
+fn read_output(mut cmd: Command) -> io::Result<String> {
+    let output = cmd.stderr(Stdio::null()).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("{:?} failed with {}", cmd.get_program(), output.status)));
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    Ok(text.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(windows)]
+fn paste_command(_primary: bool) -> Command {
+    let mut cmd = Command::new("powershell");
+    cmd.args(["-NoProfile", "-Command", "Get-Clipboard"]);
+    cmd
+}
+
+// The primary selection is the currently selected text on X11 and Wayland
+#[cfg(not(windows))]
+fn paste_command(primary: bool) -> Command {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut cmd = Command::new("wl-paste");
+        cmd.arg("--no-newline");
+        if primary {
+            cmd.arg("--primary");
+        }
+        cmd
+    } else {
+        let mut cmd = Command::new("xclip");
+        cmd.args(["-o", "-selection", if primary { "primary" } else { "clipboard" }]);
+        cmd
+    }
+}
@@ -0,0 +1,284 @@
+// explain.rs
+use crate::action::Action;
+use crate::executor::{CommandEnv, Executor};
+use crate::launcher::{Launcher, Plan, FRECENCY_WEIGHT};
+use crate::suggestion::Suggestion;
+use crate::websearch::{keyword_search, search_url};
+use command_config_parser::ExecMode;
+use std::fmt;
+
+/// How a query resolves, from the ranked candidates down to the exact
+/// process that would start. Built by [`Launcher::explain`], which runs
+/// nothing.
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    pub query: String,
+    pub candidates: Vec<Candidate>,
+    // The plan for the top candidate, or why it could not be made
+    pub plan: Option<Result<Plan, String>>,
+    // What the planned command's process would start with
+    pub environment: Option<CommandEnv>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub suggestion: Suggestion,
+    // `suggestion.score` split into the provider's match score and the
+    // launch history bonus
+    pub match_score: f64,
+    pub history_bonus: f64,
+    pub reason: String,
+}
+
+impl<E: Executor> Launcher<E> {
+    pub fn explain(&self, query: &str) -> Explanation {
+        let suggestions = self.suggest(query);
+        let plan = suggestions.first().map(|chosen| {
+            let invocation = self.prepare(query, chosen);
+            self.plan(query, chosen, invocation.as_ref()).map_err(|err| err.to_string())
+        });
+        let environment = plan
+            .as_ref()
+            .and_then(|plan| plan.as_ref().ok()?.command.as_ref())
+            .map(|command| CommandEnv::new(command, &|name| self.executor().env_var(name)));
+
+        let history = self.history();
+        let candidates = suggestions
+            .into_iter()
+            .map(|suggestion| {
+                let history_bonus = FRECENCY_WEIGHT * history.frecency(&suggestion.action.key(), query);
+                Candidate {
+                    match_score: suggestion.score - history_bonus,
+                    history_bonus,
+                    reason: self.reason(query, &suggestion),
+                    suggestion,
+                }
+            })
+            .collect();
+
+        Explanation {
+            query: query.to_string(),
+            candidates,
+            plan,
+            environment,
+        }
+    }
+
+    fn reason(&self, query: &str, suggestion: &Suggestion) -> String {
+        if !suggestion.highlights.is_empty() {
+            return format!("matched \"{}\"", bracketed(&suggestion.title, suggestion));
+        }
+
+        match suggestion.action {
+            Action::CancelProcess(pid) => format!("running process {} under `kill`", pid),
+            Action::Explain(_) => "`explain` keyword".to_string(),
+            Action::ShellCommand(_) if suggestion.title == query => "nothing else matched; run as a command line".to_string(),
+            // Websites matched by name have highlights, so this is a search
+            Action::OpenUrl(ref url) if !query.is_empty() => match keyword_search(self.registry(), query) {
+                Some((engine, terms)) if *url == search_url(&engine, &terms) => {
+                    format!("`{}` search keyword", engine.keyword)
+                }
+                _ => "nothing else matched; search the default engine".to_string(),
+            },
+            Action::RegistryCommand(ref name) => {
+                let Some(registry) = self.registry() else {
+                    return "matched by its provider".to_string();
+                };
+                let query = query.trim();
+                let word = query.split_whitespace().next().unwrap_or_default();
+                // Typed exactly before its arguments, so found by that word
+                if query != word {
+                    return match registry.get_command(word) {
+                        Some(command) if command.name != word => format!("alias `{}` followed by its arguments", word),
+                        _ => "command name followed by its arguments".to_string(),
+                    };
+                }
+                let key = registry
+                    .search_keys(query)
+                    .into_iter()
+                    .find(|(command, _, _)| command.name == *name)
+                    .map(|(_, key, _)| key);
+                match key {
+                    Some(key) if key != name => format!("matched alias `{}`", key),
+                    Some(_) => format!("matched command name `{}`", name),
+                    None => "listed without a query".to_string(),
+                }
+            }
+            _ if query.is_empty() => "listed without a query".to_string(),
+            _ => "matched by its provider".to_string(),
+        }
+    }
+}
+
+// "taskmanager" matched by "tm" reads as "[t]ask[m]anager"
+fn bracketed(title: &str, suggestion: &Suggestion) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for range in &suggestion.highlights {
+        if range.start < last || range.end > title.len() {
+            continue;
+        }
+        out.push_str(&title[last..range.start]);
+        out.push('[');
+        out.push_str(&title[range.clone()]);
+        out.push(']');
+        last = range.end;
+    }
+    out.push_str(&title[last..]);
+    out
+}
+
+// The process the system executor would start for a plan
+fn describe(plan: &Plan, environment: Option<&CommandEnv>, out: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let Some(ref command) = plan.command {
+        let mut argv = vec![command.executable.clone()];
+        argv.extend(command.args.iter().cloned());
+        if let Some((program, flags)) = command.exec.shell() {
+            argv = std::iter::once(program.to_string())
+                .chain(flags.iter().map(|flag| flag.to_string()))
+                .chain(std::iter::once(command.exec.script(&command.executable, &command.args)))
+                .collect();
+        }
+
+        writeln!(out, "  executable:  {}", argv[0])?;
+        writeln!(out, "  argv:        {:?}", argv)?;
+        writeln!(out, "  working dir: {}", command.working_dir.as_deref().unwrap_or("(launcher's)"))?;
+        if let Some(environment) = environment {
+            let base = if environment.inherit { "the launcher's, plus" } else { "only" };
+            writeln!(out, "  environment: {:?}, {}", command.env_mode, base)?;
+            for (name, value) in &environment.vars {
+                writeln!(out, "    {}={}", name, value)?;
+            }
+        }
+        if let Some(timeout) = command.timeout {
+            writeln!(out, "  timeout:     {}s, then {:?}", timeout, command.kill_signal)?;
+        }
+    } else {
+        match plan.action {
+            Action::LaunchExecutable(ref path) => {
+                writeln!(out, "  executable:  {}", path)?;
+                writeln!(out, "  argv:        {:?}", [path])?;
+            }
+            Action::ShellCommand(ref text) => {
+                let (program, flags) = ExecMode::platform_shell().shell().unwrap_or(("sh", &["-c"]));
+                let argv: Vec<&str> = std::iter::once(program).chain(flags.iter().copied()).chain([text.as_str()]).collect();
+                writeln!(out, "  executable:  {}", program)?;
+                writeln!(out, "  argv:        {:?}", argv)?;
+            }
+            Action::OpenUrl(ref url) => writeln!(out, "  opens {} with the default browser", url)?,
+            Action::CopyText(ref text) => writeln!(out, "  copies {:?} to the clipboard", text)?,
+            Action::CancelProcess(pid) => writeln!(out, "  stops process {} and its group", pid)?,
+            Action::Explain(ref query) => writeln!(out, "  explains {:?}", query)?,
+            Action::RegistryCommand(ref name) => writeln!(out, "  runs command `{}`", name)?,
+        }
+    }
+
+    if let Some(ref confirmation) = plan.confirmation {
+        writeln!(out, "  asks first ({:?} danger): {}", confirmation.level, confirmation.command_line)?;
+        for reason in &confirmation.reasons {
+            writeln!(out, "    • {}", reason)?;
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Query: {:?}", self.query)?;
+        writeln!(f, "Candidates:")?;
+        for (i, candidate) in self.candidates.iter().enumerate() {
+            write!(f, "  {}. {}  score {:.1}", i + 1, candidate.suggestion, candidate.suggestion.score)?;
+            if candidate.history_bonus != 0.0 {
+                write!(f, " (match {:.1} + history {:.1})", candidate.match_score, candidate.history_bonus)?;
+            }
+            writeln!(f)?;
+            writeln!(f, "     {}", candidate.reason)?;
+        }
+
+        match self.plan {
+            None => writeln!(f, "Nothing to run.")?,
+            Some(Err(ref err)) => writeln!(f, "Chosen: {:?}\n  cannot run yet: {}", self.candidates[0].suggestion.action, err)?,
+            Some(Ok(ref plan)) => {
+                writeln!(f, "Chosen: {:?}", plan.action)?;
+                describe(plan, self.environment.as_ref(), f)?;
+            }
+        }
+        write!(f, "Nothing was executed.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::History;
+    use crate::provider::BuiltinProvider;
+    use crate::SystemExecutor;
+    use command_config_parser::CommandRegistry;
+    use std::fs;
+
+    #[test]
+    fn test_explain_registry_command() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("commands.json");
+        fs::write(
+            &path,
+            r#"{ "version": "1.0", "commands": [
+                { "name": "deploy", "description": "Ship it", "executable": "deploy.sh",
+                  "args": ["--env={env}"], "aliases": ["dp"], "working_dir": "/srv/{env}",
+                  "env_vars": { "STAGE": "{env}" }, "env_mode": "replace", "danger_level": "medium" },
+                { "name": "release", "description": "", "executable": "release.sh", "args": [],
+                  "aliases": ["shop", "sp"] }
+            ] }"#,
+        )
+        .unwrap();
+
+        let mut launcher = Launcher::with_executor(SystemExecutor);
+        launcher.add_provider(BuiltinProvider);
+        launcher.set_registry(CommandRegistry::load_from_file(&path).unwrap());
+        let mut history = History::new();
+        history.record("dp", "command:deploy").unwrap();
+        launcher.set_history(history);
+
+        let explanation = launcher.explain("dp prod");
+        let top = &explanation.candidates[0];
+        assert_eq!(top.reason, "alias `dp` followed by its arguments");
+        assert!(top.history_bonus > 0.0);
+        assert!((top.match_score + top.history_bonus - top.suggestion.score).abs() < 1e-9);
+
+        let text = explanation.to_string();
+        assert!(text.contains(r#"argv:        ["deploy.sh", "--env=prod"]"#), "{}", text);
+        assert!(text.contains("working dir: /srv/prod"));
+        assert!(text.contains("environment: Replace, only\n"), "{}", text);
+        assert!(text.contains("    STAGE=prod"));
+        assert!(text.contains("    PATH="));
+        assert!(text.contains("asks first (Medium danger): deploy.sh --env=prod"));
+        assert!(text.ends_with("Nothing was executed."));
+        assert!(launcher.running().is_empty());
+
+        // The alias that matched best, not the first one that matches
+        let explanation = launcher.explain("sp");
+        let release = explanation.candidates.iter().find(|c| c.suggestion.title == "release").unwrap();
+        assert_eq!(release.reason, "matched alias `sp`");
+
+        // Missing values are reported instead of prompted for
+        let text = launcher.explain("deploy").to_string();
+        assert!(text.contains("cannot run yet: `deploy` needs a value for `env`"), "{}", text);
+    }
+
+    #[test]
+    fn test_explain_reasons() {
+        let mut launcher = Launcher::with_executor(SystemExecutor);
+        launcher.add_provider(BuiltinProvider);
+
+        let explanation = launcher.explain("tm");
+        assert_eq!(explanation.candidates[0].reason, "matched \"[t]ask[m]anager\"");
+
+        let explanation = launcher.explain("echo hi");
+        assert_eq!(explanation.candidates[0].reason, "nothing else matched; run as a command line");
+        assert_eq!(explanation.candidates[1].reason, "nothing else matched; search the default engine");
+        assert!(explanation.to_string().contains(r#"argv:        ["#));
+
+        let explanation = launcher.explain("gh totalcontrol");
+        assert_eq!(explanation.candidates[0].reason, "`gh` search keyword");
+    }
+}
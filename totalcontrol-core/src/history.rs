@@ -0,0 +1,253 @@
+// history.rs
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Launches older than this count half as much
+const HALF_LIFE_SECS: f64 = 3.0 * 24.0 * 60.0 * 60.0;
+// Extra weight when the same query previously picked the same item
+const QUERY_AFFINITY: f64 = 2.0;
+// Only the most recent launches are kept on disk
+const MAX_RECORDS: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaunchRecord {
+    pub query: String,
+    pub key: String,
+    pub timestamp: u64,
+}
+
+/// Launch history, stored as one JSON record per line.
+#[derive(Debug, Default)]
+pub struct History {
+    records: Vec<LaunchRecord>,
+    path: Option<PathBuf>,
+    scores: Scores,
+}
+
+// Launch weights summed per key and query, decayed to `reference`, so
+// ranking a suggestion does not scan every record. Decay is exponential,
+// so the sums only need scaling to move to a later time.
+#[derive(Debug, Default)]
+struct Scores {
+    reference: u64,
+    by_key: HashMap<String, HashMap<String, f64>>,
+}
+
+impl Scores {
+    fn new(records: &[LaunchRecord]) -> Self {
+        let mut scores = Self::default();
+        for record in records {
+            scores.add(record);
+        }
+        scores
+    }
+
+    fn add(&mut self, record: &LaunchRecord) {
+        if record.timestamp > self.reference {
+            let factor = decay(record.timestamp - self.reference);
+            for weight in self.by_key.values_mut().flat_map(HashMap::values_mut) {
+                *weight *= factor;
+            }
+            self.reference = record.timestamp;
+        }
+        let weight = decay(self.reference - record.timestamp);
+        *self
+            .by_key
+            .entry(record.key.clone())
+            .or_default()
+            .entry(record.query.clone())
+            .or_default() += weight;
+    }
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut records = Vec::new();
+
+        match File::open(path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    // Skip lines we cannot parse rather than losing the whole history
+                    if let Ok(record) = serde_json::from_str(&line?) {
+                        records.push(record);
+                    }
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+
+        let mut history = Self {
+            scores: Scores::new(&records),
+            records,
+            path: Some(path.to_path_buf()),
+        };
+        if history.records.len() > MAX_RECORDS {
+            history.compact()?;
+        }
+
+        Ok(history)
+    }
+
+    pub fn load_from_default() -> io::Result<Self> {
+        match Self::default_path() {
+            Some(path) => Self::load_from_file(path),
+            None => Ok(Self::new()),
+        }
+    }
+
+    // %APPDATA%\totalcontrol on Windows, $XDG_DATA_HOME/totalcontrol elsewhere
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = if cfg!(windows) {
+            std::env::var_os("APPDATA").map(PathBuf::from)
+        } else {
+            std::env::var_os("XDG_DATA_HOME")
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        };
+
+        dir.map(|dir| dir.join("totalcontrol").join("history.jsonl"))
+    }
+
+    pub fn records(&self) -> &[LaunchRecord] {
+        &self.records
+    }
+
+    pub fn record(&mut self, query: &str, key: &str) -> io::Result<()> {
+        self.record_at(query, key, now())
+    }
+
+    pub fn record_at(&mut self, query: &str, key: &str, timestamp: u64) -> io::Result<()> {
+        let record = LaunchRecord {
+            query: query.to_lowercase(),
+            key: key.to_string(),
+            timestamp,
+        };
+
+        if let Some(ref path) = self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        }
+
+        self.scores.add(&record);
+        self.records.push(record);
+        if self.records.len() > MAX_RECORDS * 2 {
+            self.compact()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn frecency(&self, key: &str, query: &str) -> f64 {
+        self.frecency_at(key, query, now())
+    }
+
+    // Sum of exponentially decayed launches of `key`, boosted for launches
+    // that were made from the same query. Launches after `now` count as
+    // made at the newest one.
+    pub fn frecency_at(&self, key: &str, query: &str, now: u64) -> f64 {
+        let Some(queries) = self.scores.by_key.get(key) else {
+            return 0.0;
+        };
+        let query = query.to_lowercase();
+
+        let weight: f64 = queries
+            .iter()
+            .map(|(launched_from, weight)| {
+                if !query.is_empty() && launched_from.starts_with(&query) {
+                    weight * QUERY_AFFINITY
+                } else {
+                    *weight
+                }
+            })
+            .sum();
+        weight * decay(now.saturating_sub(self.scores.reference))
+    }
+
+    // Drop the oldest records and rewrite the file
+    fn compact(&mut self) -> io::Result<()> {
+        let excess = self.records.len().saturating_sub(MAX_RECORDS);
+        self.records.drain(..excess);
+        self.scores = Scores::new(&self.records);
+
+        if let Some(ref path) = self.path {
+            let mut file = File::create(path)?;
+            for record in &self.records {
+                writeln!(file, "{}", serde_json::to_string(record)?)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn decay(age: u64) -> f64 {
+    0.5f64.powf(age as f64 / HALF_LIFE_SECS)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    #[test]
+    fn test_history_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join("history.jsonl");
+
+        let mut history = History::load_from_file(&path).unwrap();
+        history.record_at("calc", "exe:calc.exe", 100).unwrap();
+        history.record_at("Note", "exe:notepad.exe", 200).unwrap();
+
+        let reloaded = History::load_from_file(&path).unwrap();
+        assert_eq!(reloaded.records(), history.records());
+        assert_eq!(reloaded.records()[1].query, "note");
+    }
+
+    #[test]
+    fn test_frecency() {
+        let mut history = History::new();
+        let now = 100 * DAY;
+
+        history.record_at("c", "exe:calc.exe", now - DAY).unwrap();
+        history.record_at("c", "exe:calc.exe", now - DAY).unwrap();
+        history.record_at("c", "exe:cmd.exe", now - 30 * DAY).unwrap();
+        history.record_at("cmd", "exe:cmd.exe", now - 30 * DAY).unwrap();
+
+        // Frequent and recent beats old
+        let calc = history.frecency_at("exe:calc.exe", "", now);
+        let cmd = history.frecency_at("exe:cmd.exe", "", now);
+        assert!(calc > cmd);
+        assert_eq!(history.frecency_at("exe:paint.exe", "", now), 0.0);
+
+        // A launch from the same query counts extra
+        assert!(history.frecency_at("exe:cmd.exe", "cm", now) > cmd);
+
+        // Precomputed sums match decaying each launch on its own
+        let expected = 2.0 * 0.5f64.powf(DAY as f64 / HALF_LIFE_SECS);
+        assert!((calc - expected).abs() < 1e-9);
+        history.record_at("c", "exe:calc.exe", now - 2 * DAY).unwrap();
+        let older = 0.5f64.powf(2.0 * DAY as f64 / HALF_LIFE_SECS);
+        assert!((history.frecency_at("exe:calc.exe", "", now) - (expected + older)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,250 @@
+// hotkey.rs
+use command_config_parser::{HotkeyBinding, HotkeySpec};
+use std::sync::mpsc::Sender;
+
+#[cfg(target_os = "linux")]
+mod evdev;
+#[cfg(windows)]
+mod win32;
+#[cfg(target_os = "linux")]
+mod x11;
+
+#[cfg(windows)]
+pub use win32::key_for_virtual_key;
+
+// Names the backend to use instead of the first one that works
+pub const BACKEND_VAR: &str = "TOTALCONTROL_HOTKEY_BACKEND";
+
+// Backends for this platform, in the order they are tried
+#[cfg(windows)]
+pub const BACKENDS: &[&str] = &["win32"];
+#[cfg(target_os = "linux")]
+pub const BACKENDS: &[&str] = &["x11"];
+#[cfg(not(any(windows, target_os = "linux")))]
+pub const BACKENDS: &[&str] = &[];
+
+// Backends used only when named by `TOTALCONTROL_HOTKEY_BACKEND`; evdev
+// reads every key typed on every keyboard, not just the hotkeys
+#[cfg(target_os = "linux")]
+pub const OPT_IN_BACKENDS: &[&str] = &["evdev"];
+#[cfg(not(target_os = "linux"))]
+pub const OPT_IN_BACKENDS: &[&str] = &[];
+
+/// Why a hotkey could not be registered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HotkeyError {
+    // Another application already owns the combination
+    Taken,
+    Failed(String),
+}
+
+/// A source of global key presses. A backend is created, used and dropped
+/// on the thread that runs [`HotkeyManager::run`], since the platform
+/// APIs tie grabs to the thread or connection that made them.
+pub trait HotkeyBackend {
+    fn name(&self) -> &'static str;
+
+    // Deliver presses of `keys` from `wait` as `id`
+    fn register(&mut self, id: u32, keys: &HotkeySpec) -> Result<(), HotkeyError>;
+
+    // Block until a registered hotkey is pressed; `None` once no more
+    // presses can arrive
+    fn wait(&mut self) -> Option<u32>;
+}
+
+pub fn open_backend(name: &str) -> Result<Box<dyn HotkeyBackend>, String> {
+    match name {
+        #[cfg(windows)]
+        "win32" => Ok(Box::<win32::Win32Backend>::default()),
+        #[cfg(target_os = "linux")]
+        "x11" => Ok(Box::new(x11::X11Backend::new()?)),
+        #[cfg(target_os = "linux")]
+        "evdev" => Ok(Box::new(evdev::EvdevBackend::new()?)),
+        _ => {
+            let available: Vec<&str> = BACKENDS.iter().chain(OPT_IN_BACKENDS).copied().collect();
+            Err(format!("unknown hotkey backend `{}`; available: {}", name, available.join(", ")))
+        }
+    }
+}
+
+/// The backend named by `TOTALCONTROL_HOTKEY_BACKEND`, otherwise the
+/// first of [`BACKENDS`] that can be opened. [`OPT_IN_BACKENDS`] are
+/// never picked on their own.
+pub fn select_backend() -> Result<Box<dyn HotkeyBackend>, String> {
+    if let Some(name) = std::env::var(BACKEND_VAR).ok().filter(|name| !name.is_empty()) {
+        return open_backend(&name);
+    }
+
+    let mut problems = Vec::new();
+    for name in BACKENDS {
+        match open_backend(name) {
+            Ok(backend) => return Ok(backend),
+            Err(err) => problems.push(format!("{}: {}", name, err)),
+        }
+    }
+    if problems.is_empty() {
+        return Err("global hotkeys are not supported on this platform".to_string());
+    }
+    let mut message = format!("no hotkey backend could be opened ({})", problems.join("; "));
+    if let Some(name) = OPT_IN_BACKENDS.first() {
+        message.push_str(&format!("; set {}={} to use it instead", BACKEND_VAR, name));
+    }
+    Err(message)
+}
+
+/// Registers every binding with a backend and reports which one fired.
+pub struct HotkeyManager {
+    backend: Box<dyn HotkeyBackend>,
+    bindings: Vec<HotkeyBinding>,
+}
+
+impl HotkeyManager {
+    pub fn new(backend: Box<dyn HotkeyBackend>, bindings: Vec<HotkeyBinding>) -> Self {
+        Self { backend, bindings }
+    }
+
+    // Registers the bindings, then sends each press to `fired` until the
+    // backend stops or nobody listens. A binding that cannot be registered
+    // is reported through `errors`; the others still work.
+    pub fn run(mut self, fired: Sender<HotkeyBinding>, errors: Sender<String>) {
+        let mut registered = 0;
+        for (i, binding) in self.bindings.iter().enumerate() {
+            // Ids start at 1, in configuration order
+            match self.backend.register(i as u32 + 1, &binding.keys) {
+                Ok(()) => registered += 1,
+                Err(err) => {
+                    let message = match err {
+                        HotkeyError::Taken => format!(
+                            "{} is already used by another application; bind a different key in the command configuration",
+                            binding.keys
+                        ),
+                        HotkeyError::Failed(reason) => format!("failed to register {}: {}", binding.keys, reason),
+                    };
+                    errors.send(message).ok();
+                }
+            }
+        }
+        if registered == 0 {
+            return;
+        }
+
+        while let Some(id) = self.backend.wait() {
+            let Some(binding) = (id as usize).checked_sub(1).and_then(|i| self.bindings.get(i)) else {
+                continue;
+            };
+            if fired.send(binding.clone()).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use command_config_parser::HotkeyAction;
+    use std::sync::mpsc::{self, Receiver};
+
+    // A backend driven by hand: each `MockKeyboard::press` is a key press,
+    // and `wait` ends once every keyboard is dropped
+    struct MockBackend {
+        presses: Receiver<HotkeySpec>,
+        registered: Vec<(u32, HotkeySpec)>,
+        taken: Vec<HotkeySpec>,
+    }
+
+    #[derive(Clone)]
+    struct MockKeyboard {
+        presses: Sender<HotkeySpec>,
+    }
+
+    impl MockBackend {
+        fn new() -> (Self, MockKeyboard) {
+            let (presses, receiver) = mpsc::channel();
+            let backend = Self {
+                presses: receiver,
+                registered: Vec::new(),
+                taken: Vec::new(),
+            };
+            (backend, MockKeyboard { presses })
+        }
+
+        // Pretend another application already owns `keys`
+        fn with_taken(mut self, keys: HotkeySpec) -> Self {
+            self.taken.push(keys);
+            self
+        }
+    }
+
+    impl MockKeyboard {
+        fn press(&self, keys: HotkeySpec) {
+            self.presses.send(keys).ok();
+        }
+    }
+
+    impl HotkeyBackend for MockBackend {
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+
+        fn register(&mut self, id: u32, keys: &HotkeySpec) -> Result<(), HotkeyError> {
+            if self.taken.contains(keys) {
+                return Err(HotkeyError::Taken);
+            }
+            self.registered.push((id, *keys));
+            Ok(())
+        }
+
+        fn wait(&mut self) -> Option<u32> {
+            loop {
+                let keys = self.presses.recv().ok()?;
+                // Keys nobody registered go to other applications
+                if let Some((id, _)) = self.registered.iter().find(|(_, registered)| *registered == keys) {
+                    return Some(*id);
+                }
+            }
+        }
+    }
+
+    fn binding(keys: &str, action: HotkeyAction) -> HotkeyBinding {
+        HotkeyBinding {
+            keys: keys.parse().unwrap(),
+            action,
+        }
+    }
+
+    #[test]
+    fn test_manager_reports_fired_bindings() {
+        let bindings = vec![
+            binding("Ctrl+Space", HotkeyAction::Launcher),
+            binding("Super+G", HotkeyAction::Category("development".to_string())),
+            binding("F13", HotkeyAction::Clipboard),
+        ];
+        let (backend, keyboard) = MockBackend::new();
+        let backend = backend.with_taken("F13".parse().unwrap());
+
+        keyboard.press("Super+G".parse().unwrap());
+        keyboard.press("Ctrl+Alt+X".parse().unwrap());
+        keyboard.press("F13".parse().unwrap());
+        keyboard.press("ctrl+space".parse().unwrap());
+        drop(keyboard);
+
+        let (fired, presses) = mpsc::channel();
+        let (errors, problems) = mpsc::channel();
+        HotkeyManager::new(Box::new(backend), bindings.clone()).run(fired, errors);
+
+        let presses: Vec<HotkeyBinding> = presses.try_iter().collect();
+        assert_eq!(presses, [bindings[1].clone(), bindings[0].clone()]);
+        let problems: Vec<String> = problems.try_iter().collect();
+        assert_eq!(
+            problems,
+            ["F13 is already used by another application; bind a different key in the command configuration"]
+        );
+    }
+
+    #[test]
+    fn test_open_backend() {
+        let err = open_backend("mock").err().unwrap();
+        assert!(err.starts_with("unknown hotkey backend `mock`"), "{}", err);
+    }
+}
@@ -0,0 +1,236 @@
+// evdev.rs
+use super::{HotkeyBackend, HotkeyError};
+use command_config_parser::{HotkeySpec, Key, Modifiers};
+use evdev::{Device, EventSummary, KeyCode};
+use std::collections::VecDeque;
+use std::io;
+use std::os::fd::AsRawFd;
+
+const LETTERS: [KeyCode; 26] = [
+    KeyCode::KEY_A, KeyCode::KEY_B, KeyCode::KEY_C, KeyCode::KEY_D, KeyCode::KEY_E, KeyCode::KEY_F,
+    KeyCode::KEY_G, KeyCode::KEY_H, KeyCode::KEY_I, KeyCode::KEY_J, KeyCode::KEY_K, KeyCode::KEY_L,
+    KeyCode::KEY_M, KeyCode::KEY_N, KeyCode::KEY_O, KeyCode::KEY_P, KeyCode::KEY_Q, KeyCode::KEY_R,
+    KeyCode::KEY_S, KeyCode::KEY_T, KeyCode::KEY_U, KeyCode::KEY_V, KeyCode::KEY_W, KeyCode::KEY_X,
+    KeyCode::KEY_Y, KeyCode::KEY_Z,
+];
+
+// Raw key events from every keyboard under /dev/input, for Wayland and
+// consoles where nothing can grab keys. Reading needs access to the
+// devices (usually the `input` group). Keys are not taken from other
+// applications, so a combination can never be reported as already used.
+pub struct EvdevBackend {
+    devices: Vec<Device>,
+    hotkeys: Vec<(u32, KeyCode, Modifiers)>,
+    // Modifier keys currently down, left and right counted apart
+    held: Vec<KeyCode>,
+    // Presses read in the same batch as an earlier one
+    fired: VecDeque<u32>,
+}
+
+impl EvdevBackend {
+    pub fn new() -> Result<Self, String> {
+        let devices: Vec<Device> = evdev::enumerate()
+            .map(|(_, device)| device)
+            .filter(|device| {
+                device
+                    .supported_keys()
+                    .is_some_and(|keys| keys.contains(KeyCode::KEY_A) && keys.contains(KeyCode::KEY_SPACE))
+            })
+            .collect();
+        if devices.is_empty() {
+            return Err("no keyboard under /dev/input can be read (is the user in the `input` group?)".to_string());
+        }
+        Ok(Self {
+            devices,
+            hotkeys: Vec::new(),
+            held: Vec::new(),
+            fired: VecDeque::new(),
+        })
+    }
+
+    fn modifiers(&self) -> Modifiers {
+        let down = |codes: [KeyCode; 2]| self.held.iter().any(|code| codes.contains(code));
+        Modifiers {
+            ctrl: down([KeyCode::KEY_LEFTCTRL, KeyCode::KEY_RIGHTCTRL]),
+            alt: down([KeyCode::KEY_LEFTALT, KeyCode::KEY_RIGHTALT]),
+            shift: down([KeyCode::KEY_LEFTSHIFT, KeyCode::KEY_RIGHTSHIFT]),
+            super_key: down([KeyCode::KEY_LEFTMETA, KeyCode::KEY_RIGHTMETA]),
+        }
+    }
+
+    // Track modifiers and queue every hotkey pressed in one batch of events
+    fn handle(&mut self, code: KeyCode, value: i32) {
+        if is_modifier(code) {
+            self.held.retain(|held| *held != code);
+            if value != 0 {
+                self.held.push(code);
+            }
+            return;
+        }
+        // 1 is a press; 2 is auto-repeat and 0 a release
+        if value != 1 {
+            return;
+        }
+        let held = self.modifiers();
+        let ids = self
+            .hotkeys
+            .iter()
+            .filter(|(_, hotkey, modifiers)| *hotkey == code && *modifiers == held)
+            .map(|(id, _, _)| *id);
+        self.fired.extend(ids);
+    }
+}
+
+impl HotkeyBackend for EvdevBackend {
+    fn name(&self) -> &'static str {
+        "evdev"
+    }
+
+    fn register(&mut self, id: u32, keys: &HotkeySpec) -> Result<(), HotkeyError> {
+        let code = key_code(keys.key).ok_or_else(|| HotkeyError::Failed(format!("no key code for {}", keys.key)))?;
+        self.hotkeys.push((id, code, keys.modifiers));
+        Ok(())
+    }
+
+    fn wait(&mut self) -> Option<u32> {
+        loop {
+            if let Some(id) = self.fired.pop_front() {
+                return Some(id);
+            }
+            if self.devices.is_empty() {
+                return None;
+            }
+
+            let mut fds: Vec<libc::pollfd> = self
+                .devices
+                .iter()
+                .map(|device| libc::pollfd {
+                    fd: device.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                })
+                .collect();
+            if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } < 0 {
+                if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return None;
+            }
+
+            let mut unplugged = Vec::new();
+            for (i, fd) in fds.iter().enumerate().rev() {
+                if fd.revents & (libc::POLLERR | libc::POLLHUP | libc::POLLNVAL) != 0 {
+                    unplugged.push(i);
+                    continue;
+                }
+                if fd.revents & libc::POLLIN == 0 {
+                    continue;
+                }
+                let keys: Vec<(KeyCode, i32)> = match self.devices[i].fetch_events() {
+                    Ok(events) => events
+                        .filter_map(|event| match event.destructure() {
+                            EventSummary::Key(_, code, value) => Some((code, value)),
+                            _ => None,
+                        })
+                        .collect(),
+                    Err(_) => {
+                        unplugged.push(i);
+                        continue;
+                    }
+                };
+                for (code, value) in keys {
+                    self.handle(code, value);
+                }
+            }
+            // Indices were collected last to first, so removal is safe
+            for i in unplugged {
+                self.devices.remove(i);
+            }
+        }
+    }
+}
+
+fn is_modifier(code: KeyCode) -> bool {
+    [
+        KeyCode::KEY_LEFTCTRL,
+        KeyCode::KEY_RIGHTCTRL,
+        KeyCode::KEY_LEFTALT,
+        KeyCode::KEY_RIGHTALT,
+        KeyCode::KEY_LEFTSHIFT,
+        KeyCode::KEY_RIGHTSHIFT,
+        KeyCode::KEY_LEFTMETA,
+        KeyCode::KEY_RIGHTMETA,
+    ]
+    .contains(&code)
+}
+
+// Codes follow key positions on a US layout, not the characters typed
+fn key_code(key: Key) -> Option<KeyCode> {
+    let code = match key {
+        Key::Char(c @ 'A'..='Z') => LETTERS[(c as u8 - b'A') as usize],
+        Key::Char('0') => KeyCode::KEY_0,
+        Key::Char(c @ '1'..='9') => KeyCode::new(KeyCode::KEY_1.code() + (c as u16 - '1' as u16)),
+        Key::Char(_) => return None,
+        Key::F(n @ 1..=10) => KeyCode::new(KeyCode::KEY_F1.code() + u16::from(n) - 1),
+        Key::F(11) => KeyCode::KEY_F11,
+        Key::F(12) => KeyCode::KEY_F12,
+        Key::F(n @ 13..=24) => KeyCode::new(KeyCode::KEY_F13.code() + u16::from(n) - 13),
+        Key::F(_) => return None,
+        Key::Space => KeyCode::KEY_SPACE,
+        Key::Enter => KeyCode::KEY_ENTER,
+        Key::Tab => KeyCode::KEY_TAB,
+        Key::Escape => KeyCode::KEY_ESC,
+        Key::Backspace => KeyCode::KEY_BACKSPACE,
+        Key::Delete => KeyCode::KEY_DELETE,
+        Key::Insert => KeyCode::KEY_INSERT,
+        Key::Home => KeyCode::KEY_HOME,
+        Key::End => KeyCode::KEY_END,
+        Key::PageUp => KeyCode::KEY_PAGEUP,
+        Key::PageDown => KeyCode::KEY_PAGEDOWN,
+        Key::Up => KeyCode::KEY_UP,
+        Key::Down => KeyCode::KEY_DOWN,
+        Key::Left => KeyCode::KEY_LEFT,
+        Key::Right => KeyCode::KEY_RIGHT,
+        Key::PrintScreen => KeyCode::KEY_SYSRQ,
+        Key::Pause => KeyCode::KEY_PAUSE,
+    };
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_codes_and_modifiers() {
+        let code = |text: &str| key_code(text.parse::<HotkeySpec>().unwrap().key);
+        assert_eq!(code("Ctrl+Q"), Some(KeyCode::KEY_Q));
+        assert_eq!(code("Ctrl+7"), Some(KeyCode::KEY_7));
+        assert_eq!(code("F10"), Some(KeyCode::KEY_F10));
+        assert_eq!(code("F13"), Some(KeyCode::KEY_F13));
+        assert_eq!(code("F24"), Some(KeyCode::KEY_F24));
+
+        // Fed events by hand, without opening any device
+        let mut backend = EvdevBackend {
+            devices: Vec::new(),
+            hotkeys: Vec::new(),
+            held: Vec::new(),
+            fired: VecDeque::new(),
+        };
+        backend.register(1, &"Ctrl+Shift+Space".parse().unwrap()).unwrap();
+        backend.register(2, &"Super+P".parse().unwrap()).unwrap();
+
+        backend.handle(KeyCode::KEY_RIGHTCTRL, 1);
+        backend.handle(KeyCode::KEY_SPACE, 1);
+        backend.handle(KeyCode::KEY_LEFTSHIFT, 1);
+        backend.handle(KeyCode::KEY_SPACE, 1);
+        backend.handle(KeyCode::KEY_SPACE, 2);
+        backend.handle(KeyCode::KEY_RIGHTCTRL, 0);
+        backend.handle(KeyCode::KEY_LEFTSHIFT, 0);
+        backend.handle(KeyCode::KEY_LEFTMETA, 1);
+        backend.handle(KeyCode::KEY_P, 1);
+        assert_eq!(backend.wait(), Some(1));
+        assert_eq!(backend.wait(), Some(2));
+        assert_eq!(backend.wait(), None);
+    }
+}
@@ -0,0 +1,117 @@
+// win32.rs
+use super::{HotkeyBackend, HotkeyError};
+use command_config_parser::{HotkeySpec, Key};
+use windows::Win32::Foundation::{GetLastError, ERROR_HOTKEY_ALREADY_REGISTERED, HWND};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN,
+    VIRTUAL_KEY, VK_BACK, VK_DELETE, VK_DOWN, VK_END, VK_ESCAPE, VK_F1, VK_HOME, VK_INSERT, VK_LEFT, VK_NEXT,
+    VK_PAUSE, VK_PRIOR, VK_RETURN, VK_RIGHT, VK_SNAPSHOT, VK_SPACE, VK_TAB, VK_UP,
+};
+use windows::Win32::UI::WindowsAndMessaging::{GetMessageA, MSG, WM_HOTKEY};
+
+// `RegisterHotKey` for the current thread, with presses read from its
+// message queue
+#[derive(Default)]
+pub struct Win32Backend {
+    ids: Vec<i32>,
+}
+
+impl HotkeyBackend for Win32Backend {
+    fn name(&self) -> &'static str {
+        "win32"
+    }
+
+    fn register(&mut self, id: u32, keys: &HotkeySpec) -> Result<(), HotkeyError> {
+        let id = id as i32;
+        unsafe {
+            UnregisterHotKey(HWND(0), id);
+            if !RegisterHotKey(HWND(0), id, modifiers(keys), virtual_key(keys.key)).as_bool() {
+                let error = GetLastError();
+                if error == ERROR_HOTKEY_ALREADY_REGISTERED {
+                    return Err(HotkeyError::Taken);
+                }
+                return Err(HotkeyError::Failed(format!("error {}", error.0)));
+            }
+        }
+        self.ids.push(id);
+        Ok(())
+    }
+
+    fn wait(&mut self) -> Option<u32> {
+        let mut msg = MSG::default();
+        // 0 is WM_QUIT and -1 an error; both end the loop
+        while unsafe { GetMessageA(&mut msg, HWND(0), 0, 0) }.0 > 0 {
+            if msg.message == WM_HOTKEY {
+                return Some(msg.wParam.0 as u32);
+            }
+        }
+        None
+    }
+}
+
+impl Drop for Win32Backend {
+    fn drop(&mut self) {
+        for id in &self.ids {
+            unsafe {
+                UnregisterHotKey(HWND(0), *id);
+            }
+        }
+    }
+}
+
+fn modifiers(keys: &HotkeySpec) -> HOT_KEY_MODIFIERS {
+    // Holding the keys down fires once, not on every repeat
+    let mut flags = MOD_NOREPEAT.0;
+    let held = [
+        (keys.modifiers.ctrl, MOD_CONTROL),
+        (keys.modifiers.alt, MOD_ALT),
+        (keys.modifiers.shift, MOD_SHIFT),
+        (keys.modifiers.super_key, MOD_WIN),
+    ];
+    for (down, modifier) in held {
+        if down {
+            flags |= modifier.0;
+        }
+    }
+    HOT_KEY_MODIFIERS(flags)
+}
+
+fn virtual_key(key: Key) -> u32 {
+    let vk = match key {
+        // Letters and digits are their uppercase ASCII codes
+        Key::Char(c) => return c as u32,
+        Key::F(n) => VIRTUAL_KEY(VK_F1.0 + u16::from(n) - 1),
+        Key::Space => VK_SPACE,
+        Key::Enter => VK_RETURN,
+        Key::Tab => VK_TAB,
+        Key::Escape => VK_ESCAPE,
+        Key::Backspace => VK_BACK,
+        Key::Delete => VK_DELETE,
+        Key::Insert => VK_INSERT,
+        Key::Home => VK_HOME,
+        Key::End => VK_END,
+        Key::PageUp => VK_PRIOR,
+        Key::PageDown => VK_NEXT,
+        Key::Up => VK_UP,
+        Key::Down => VK_DOWN,
+        Key::Left => VK_LEFT,
+        Key::Right => VK_RIGHT,
+        Key::PrintScreen => VK_SNAPSHOT,
+        Key::Pause => VK_PAUSE,
+    };
+    vk.0 as u32
+}
+
+// The key a `WM_KEYDOWN` is for, as far as hotkeys can name it
+pub fn key_for_virtual_key(vk: u32) -> Option<Key> {
+    const NAMED: [Key; 17] = [
+        Key::Space, Key::Enter, Key::Tab, Key::Escape, Key::Backspace, Key::Delete, Key::Insert, Key::Home, Key::End,
+        Key::PageUp, Key::PageDown, Key::Up, Key::Down, Key::Left, Key::Right, Key::PrintScreen, Key::Pause,
+    ];
+    let f1 = u32::from(VK_F1.0);
+    match vk {
+        0x30..=0x39 | 0x41..=0x5A => char::from_u32(vk).map(Key::Char),
+        _ if (f1..f1 + 24).contains(&vk) => Some(Key::F((vk - f1 + 1) as u8)),
+        _ => NAMED.into_iter().find(|key| virtual_key(*key) == vk),
+    }
+}
@@ -0,0 +1,164 @@
+// x11.rs
+use super::{HotkeyBackend, HotkeyError};
+use command_config_parser::{HotkeySpec, Key};
+use std::os::raw::{c_int, c_uint, c_ulong};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use x11_dl::keysym::*;
+use x11_dl::xlib::{
+    BadAccess, ControlMask, Display, GrabModeAsync, KeyPress, LockMask, Mod1Mask, Mod2Mask, Mod4Mask, ShiftMask,
+    Window, XErrorEvent, XEvent, Xlib,
+};
+
+// Caps Lock and Num Lock count as modifiers in X, so every hotkey is also
+// grabbed with each combination of them
+const LOCKS: [c_uint; 4] = [0, LockMask, Mod2Mask, LockMask | Mod2Mask];
+const MODIFIERS: c_uint = ControlMask | ShiftMask | Mod1Mask | Mod4Mask;
+
+// Set by the error handler when a grab is refused; X reports errors
+// asynchronously, so `register` syncs and then checks this
+static GRAB_REFUSED: AtomicBool = AtomicBool::new(false);
+
+unsafe extern "C" fn on_error(_: *mut Display, event: *mut XErrorEvent) -> c_int {
+    if (*event).error_code == BadAccess {
+        GRAB_REFUSED.store(true, Ordering::SeqCst);
+    }
+    0
+}
+
+// `XGrabKey` on the root window. libX11 is loaded at runtime, so this
+// backend is simply unavailable without it.
+pub struct X11Backend {
+    xlib: Xlib,
+    display: *mut Display,
+    root: Window,
+    grabs: Vec<Grab>,
+}
+
+struct Grab {
+    id: u32,
+    keycode: c_int,
+    modifiers: c_uint,
+}
+
+impl X11Backend {
+    pub fn new() -> Result<Self, String> {
+        let xlib = Xlib::open().map_err(|err| format!("cannot load libX11: {}", err))?;
+        let display = unsafe { (xlib.XOpenDisplay)(ptr::null()) };
+        if display.is_null() {
+            return Err("cannot open the X display (is DISPLAY set?)".to_string());
+        }
+        let root = unsafe { (xlib.XDefaultRootWindow)(display) };
+        Ok(Self {
+            xlib,
+            display,
+            root,
+            grabs: Vec::new(),
+        })
+    }
+
+    fn ungrab(&self, keycode: c_int, modifiers: c_uint) {
+        for lock in LOCKS {
+            unsafe { (self.xlib.XUngrabKey)(self.display, keycode, modifiers | lock, self.root) };
+        }
+    }
+}
+
+impl HotkeyBackend for X11Backend {
+    fn name(&self) -> &'static str {
+        "x11"
+    }
+
+    fn register(&mut self, id: u32, keys: &HotkeySpec) -> Result<(), HotkeyError> {
+        let keycode = unsafe { (self.xlib.XKeysymToKeycode)(self.display, keysym(keys.key) as c_ulong) };
+        if keycode == 0 {
+            return Err(HotkeyError::Failed(format!("the keyboard layout has no {} key", keys.key)));
+        }
+        let keycode = c_int::from(keycode);
+        let modifiers = modifier_mask(keys);
+
+        // The handler is process-wide, so it is only ours while grabbing
+        GRAB_REFUSED.store(false, Ordering::SeqCst);
+        let previous = unsafe { (self.xlib.XSetErrorHandler)(Some(on_error)) };
+        for lock in LOCKS {
+            unsafe {
+                (self.xlib.XGrabKey)(self.display, keycode, modifiers | lock, self.root, 1, GrabModeAsync, GrabModeAsync);
+            }
+        }
+        unsafe {
+            (self.xlib.XSync)(self.display, 0);
+            (self.xlib.XSetErrorHandler)(previous);
+        }
+        if GRAB_REFUSED.load(Ordering::SeqCst) {
+            self.ungrab(keycode, modifiers);
+            return Err(HotkeyError::Taken);
+        }
+
+        self.grabs.push(Grab { id, keycode, modifiers });
+        Ok(())
+    }
+
+    fn wait(&mut self) -> Option<u32> {
+        loop {
+            let mut event = XEvent { type_: 0 };
+            unsafe { (self.xlib.XNextEvent)(self.display, &mut event) };
+            if event.get_type() != KeyPress {
+                continue;
+            }
+
+            let key = unsafe { event.key };
+            let held = key.state & MODIFIERS;
+            let grab = self
+                .grabs
+                .iter()
+                .find(|grab| grab.keycode as c_uint == key.keycode && grab.modifiers == held);
+            if let Some(grab) = grab {
+                return Some(grab.id);
+            }
+        }
+    }
+}
+
+impl Drop for X11Backend {
+    fn drop(&mut self) {
+        for grab in &self.grabs {
+            self.ungrab(grab.keycode, grab.modifiers);
+        }
+        unsafe { (self.xlib.XCloseDisplay)(self.display) };
+    }
+}
+
+fn modifier_mask(keys: &HotkeySpec) -> c_uint {
+    let held = [
+        (keys.modifiers.ctrl, ControlMask),
+        (keys.modifiers.alt, Mod1Mask),
+        (keys.modifiers.shift, ShiftMask),
+        (keys.modifiers.super_key, Mod4Mask),
+    ];
+    held.iter().filter(|(down, _)| *down).fold(0, |mask, (_, bit)| mask | bit)
+}
+
+fn keysym(key: Key) -> c_uint {
+    match key {
+        // Letter keysyms are the lowercase letters
+        Key::Char(c) => c.to_ascii_lowercase() as c_uint,
+        Key::F(n) => XK_F1 + c_uint::from(n) - 1,
+        Key::Space => XK_space,
+        Key::Enter => XK_Return,
+        Key::Tab => XK_Tab,
+        Key::Escape => XK_Escape,
+        Key::Backspace => XK_BackSpace,
+        Key::Delete => XK_Delete,
+        Key::Insert => XK_Insert,
+        Key::Home => XK_Home,
+        Key::End => XK_End,
+        Key::PageUp => XK_Prior,
+        Key::PageDown => XK_Next,
+        Key::Up => XK_Up,
+        Key::Down => XK_Down,
+        Key::Left => XK_Left,
+        Key::Right => XK_Right,
+        Key::PrintScreen => XK_Print,
+        Key::Pause => XK_Pause,
+    }
+}
@@ -0,0 +1,400 @@
+// invocation.rs
+use crate::executor::Executor;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use command_config_parser::fuzzy_match;
+use command_config_parser::params::{expand, placeholders, Placeholder};
+use command_config_parser::{expand_vars, Command, EnvMode, Param};
+use std::collections::HashMap;
+use std::io;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[cfg(windows)]
+const PATH_SEPARATOR: char = ';';
+#[cfg(not(windows))]
+const PATH_SEPARATOR: char = ':';
+
+/// Parameter values for one run of a registry command, collected from the
+/// words typed after its name, declared defaults and then prompts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invocation {
+    command: String,
+    // Everything typed after the command name, for `{query}`
+    rest: String,
+    values: HashMap<String, String>,
+    // Parameters still without a value, in declaration order
+    pending: Vec<Param>,
+}
+
+impl Invocation {
+    pub fn new(command: &Command, rest: &str) -> Self {
+        let rest = rest.trim();
+        let params = command.parameters();
+        let mut values = HashMap::new();
+        let mut pending = Vec::new();
+
+        // Words fill the parameters in order, the last one taking what is
+        // left. Commands using `{query}` get the text as a whole instead.
+        let mut words: Vec<&str> = if uses_query(command) {
+            Vec::new()
+        } else {
+            rest.split_whitespace().collect()
+        };
+
+        for (i, param) in params.iter().enumerate() {
+            let given = if i + 1 == params.len() && !words.is_empty() {
+                Some(std::mem::take(&mut words).join(" "))
+            } else if !words.is_empty() {
+                Some(words.remove(0).to_string())
+            } else {
+                None
+            };
+
+            match given.as_deref().map(|value| param.check(value)) {
+                Some(Ok(value)) => {
+                    values.insert(param.name.clone(), value);
+                }
+                // A value that does not fit is asked for again
+                Some(Err(_)) => {
+                    words.clear();
+                    pending.push(param.clone());
+                }
+                None => match param.default.as_deref().map(|value| param.check(value)) {
+                    Some(Ok(value)) => {
+                        values.insert(param.name.clone(), value);
+                    }
+                    _ => pending.push(param.clone()),
+                },
+            }
+        }
+
+        Self {
+            command: command.name.clone(),
+            rest: rest.to_string(),
+            values,
+            pending,
+        }
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    // The parameter to prompt for next
+    pub fn current(&self) -> Option<&Param> {
+        self.pending.first()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn values(&self) -> &HashMap<String, String> {
+        &self.values
+    }
+
+    // Answer the current prompt; on error the prompt stays open
+    pub fn answer(&mut self, text: &str) -> Result<(), String> {
+        let Some(param) = self.pending.first() else {
+            return Ok(());
+        };
+        let value = param.check(text)?;
+        let param = self.pending.remove(0);
+        self.values.insert(param.name, value);
+        Ok(())
+    }
+
+    // Choices for the current prompt matching what has been typed so far
+    pub fn options(&self, typed: &str) -> Vec<&str> {
+        let Some(param) = self.current() else {
+            return Vec::new();
+        };
+        let mut options: Vec<(i64, &str)> = param
+            .choices
+            .iter()
+            .filter_map(|choice| {
+                let score = if typed.is_empty() { 0 } else { fuzzy_match(typed, choice)?.score };
+                Some((score, choice.as_str()))
+            })
+            .collect();
+        options.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        options.into_iter().map(|(_, choice)| choice).collect()
+    }
+
+    /// A copy of `command` ready to run: environment variables, `~` and
+    /// placeholders expanded in one pass, so a variable's value is never
+    /// read as a placeholder or a placeholder's as a variable. System state
+    /// (variables, clipboard, selection) is read through `executor`.
+    pub fn expand<E: Executor>(&self, command: &Command, executor: &E, now: DateTime<Local>) -> io::Result<Command> {
+        if let Some(param) = self.current() {
+            return Err(invalid(format!("`{}` needs a value for `{}`", self.command, param.name)));
+        }
+
+        let lookup = |name: &str| executor.env_var(name);
+        let mut clipboard = None;
+        let mut selection = None;
+        let mut resolve = |placeholder: &Placeholder| -> io::Result<String> {
+            match placeholder.name.as_str() {
+                "query" => Ok(self.rest.clone()),
+                "clipboard" => cached(&mut clipboard, || executor.read_clipboard()),
+                "selection" => cached(&mut selection, || executor.read_selection()),
+                "date" => format_date(now, placeholder.format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT)),
+                name => self
+                    .values
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| invalid(format!("no value for `{{{}}}`", name))),
+            }
+        };
+        // Shell scripts keep their variables for the shell, and every value
+        // put into them is quoted
+        let mut field = |text: &str, script: bool| -> io::Result<String> {
+            if script {
+                return expand(text, |placeholder: &Placeholder| {
+                    let value = resolve(placeholder)?;
+                    command.exec.quote(&value).map_err(|err| self.error(err))
+                });
+            }
+            expand_both(text, &lookup, &mut resolve).map_err(|err| match err {
+                Expansion::Vars(message) => self.error(message),
+                Expansion::Placeholder(err) => err,
+            })
+        };
+
+        // A script's executable is left for the shell, like its variables
+        let script = !command.exec.is_direct();
+        let mut expanded = command.clone();
+        if !script {
+            expanded.executable = expand_vars(&command.executable, &lookup).map_err(|err| self.error(err))?;
+        }
+        for arg in &mut expanded.args {
+            *arg = field(arg, script)?;
+        }
+        if let Some(ref mut dir) = expanded.working_dir {
+            *dir = field(dir, false)?;
+        }
+        for (name, value) in expanded.env_vars.iter_mut().flat_map(|vars| vars.iter_mut()) {
+            *value = field(value, false)?;
+            if command.env_mode == EnvMode::Extend {
+                if let Some(existing) = lookup(name).filter(|existing| !existing.is_empty()) {
+                    *value = format!("{}{}{}", value, PATH_SEPARATOR, existing);
+                }
+            }
+        }
+        Ok(expanded)
+    }
+
+    fn error(&self, message: String) -> io::Error {
+        invalid(format!("`{}`: {}", self.command, message))
+    }
+}
+
+enum Expansion {
+    Vars(String),
+    Placeholder(io::Error),
+}
+
+// Placeholders stand in as private-use characters while variables are
+// expanded, then are resolved; neither expansion sees the other's output
+const FIRST_MARK: u32 = 0xF0000;
+const MARKS: u32 = 0xFFFE;
+
+fn expand_both(
+    text: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+    resolve: &mut impl FnMut(&Placeholder) -> io::Result<String>,
+) -> Result<String, Expansion> {
+    let is_mark = |c: char| (FIRST_MARK..FIRST_MARK + MARKS).contains(&(c as u32));
+    let text: String = text.chars().filter(|&c| !is_mark(c)).collect();
+
+    let mut found = Vec::new();
+    let marked = expand(&text, |placeholder| {
+        let mark = char::from_u32(FIRST_MARK + found.len() as u32)
+            .filter(|_| found.len() < MARKS as usize)
+            .ok_or_else(|| Expansion::Vars("too many placeholders".to_string()))?;
+        found.push(placeholder.clone());
+        Ok(mark.to_string())
+    })?;
+    let lookup = |name: &str| lookup(name).map(|value| value.replace(is_mark, ""));
+    let vars = expand_vars(&marked, &lookup).map_err(Expansion::Vars)?;
+
+    let mut out = String::with_capacity(vars.len());
+    for c in vars.chars() {
+        if is_mark(c) {
+            let placeholder = &found[(c as u32 - FIRST_MARK) as usize];
+            out.push_str(&resolve(placeholder).map_err(Expansion::Placeholder)?);
+        } else {
+            out.push(c);
+        }
+    }
+    Ok(out)
+}
+
+/// Whether anything typed after the command's name is used.
+pub fn takes_input(command: &Command) -> bool {
+    uses_query(command) || !command.parameters().is_empty()
+}
+
+fn uses_query(command: &Command) -> bool {
+    command
+        .templates()
+        .into_iter()
+        .any(|(_, text)| placeholders(text).iter().any(|p| p.name == "query"))
+}
+
+// Read a value once per expansion, however often it is used
+fn cached(slot: &mut Option<String>, read: impl FnOnce() -> io::Result<String>) -> io::Result<String> {
+    if slot.is_none() {
+        *slot = Some(read()?);
+    }
+    Ok(slot.clone().unwrap_or_default())
+}
+
+fn format_date(now: DateTime<Local>, format: &str) -> io::Result<String> {
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(invalid(format!("invalid date format `{}`", format)));
+    }
+    Ok(now.format_with_items(items.into_iter()).to_string())
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::Action;
+    use crate::process::ProcessHandle;
+    use chrono::TimeZone;
+    use command_config_parser::{ExecMode, ParamKind};
+
+    struct Clipboard;
+
+    impl Executor for Clipboard {
+        fn execute(&self, _: &Action) -> io::Result<Option<ProcessHandle>> {
+            Ok(None)
+        }
+
+        fn run_command(&self, _: &Command) -> io::Result<Option<ProcessHandle>> {
+            Ok(None)
+        }
+
+        fn read_clipboard(&self) -> io::Result<String> {
+            Ok("copied".to_string())
+        }
+
+        fn env_var(&self, name: &str) -> Option<String> {
+            match name {
+                "HOME" => Some("/home/ada".to_string()),
+                "PATH" => Some("/usr/bin".to_string()),
+                "INJECT" => Some("{clipboard}".to_string()),
+                _ => None,
+            }
+        }
+    }
+
+    fn deploy() -> Command {
+        let mut env = Param::implicit("env");
+        env.kind = ParamKind::Choice;
+        env.choices = vec!["staging".to_string(), "prod".to_string()];
+        let mut replicas = Param::implicit("replicas");
+        replicas.kind = ParamKind::Number;
+        replicas.default = Some("2".to_string());
+
+        Command {
+            name: "deploy".to_string(),
+            description: String::new(),
+            executable: "deploy.sh".to_string(),
+            args: vec![
+                "--env={env}".to_string(),
+                "-n".to_string(),
+                "{replicas}".to_string(),
+                "{date:%Y%m%d}-{clipboard}".to_string(),
+            ],
+            exec: ExecMode::Direct,
+            working_dir: Some("/srv/{env}".to_string()),
+            env_vars: None,
+            env_mode: EnvMode::Inherit,
+            aliases: None,
+            category: None,
+            show_output: false,
+            timeout: None,
+            kill_signal: Default::default(),
+            confirm: false,
+            danger_level: Default::default(),
+            params: vec![env, replicas],
+        }
+    }
+
+    #[test]
+    fn test_prompts_for_missing_values() {
+        let command = deploy();
+
+        let mut invocation = Invocation::new(&command, "");
+        assert_eq!(invocation.current().unwrap().name, "env");
+        assert_eq!(invocation.options("pr"), vec!["prod"]);
+        assert!(invocation.answer("dev").is_err());
+        invocation.answer("prod").unwrap();
+        assert!(invocation.is_complete());
+        assert_eq!(invocation.values()["replicas"], "2");
+
+        // Typed after the name instead; a bad value is asked for again
+        assert!(Invocation::new(&command, "staging 5").is_complete());
+        assert_eq!(Invocation::new(&command, "dev 5").current().unwrap().name, "env");
+    }
+
+    #[test]
+    fn test_expand() {
+        let command = deploy();
+        let now = Local.with_ymd_and_hms(2024, 3, 9, 12, 0, 0).unwrap();
+
+        let invocation = Invocation::new(&command, "staging");
+        let expanded = invocation.expand(&command, &Clipboard, now).unwrap();
+        assert_eq!(expanded.args, ["--env=staging", "-n", "2", "20240309-copied"]);
+        assert_eq!(expanded.working_dir.as_deref(), Some("/srv/staging"));
+
+        let mut tools = command.clone();
+        tools.executable = "~/bin/deploy".to_string();
+        tools.env_vars = Some(HashMap::from([("PATH".to_string(), "${TOOLS:-/opt/tools}".to_string())]));
+        tools.env_mode = EnvMode::Extend;
+        let expanded = invocation.expand(&tools, &Clipboard, now).unwrap();
+        assert_eq!(expanded.executable, "/home/ada/bin/deploy");
+        assert_eq!(expanded.env_vars.unwrap()["PATH"], format!("/opt/tools{}/usr/bin", PATH_SEPARATOR));
+
+        tools.args.push("$TOKEN".to_string());
+        let err = invocation.expand(&tools, &Clipboard, now).unwrap_err();
+        assert!(err.to_string().starts_with("`deploy`: environment variable `TOKEN` is not set"));
+
+        let incomplete = Invocation::new(&command, "");
+        let err = incomplete.expand(&command, &Clipboard, now).unwrap_err();
+        assert_eq!(err.to_string(), "`deploy` needs a value for `env`");
+
+        // In a shell, values are quoted and variables left to the shell
+        let mut script = deploy();
+        script.exec = ExecMode::Sh;
+        script.params.clear();
+        script.working_dir = None;
+        script.executable = "echo".to_string();
+        script.args = vec!["$HOME".to_string(), "{query}".to_string(), "| wc -c".to_string()];
+        let hostile = Invocation::new(&script, "'; rm -rf ~; echo '");
+        let expanded = hostile.expand(&script, &Clipboard, now).unwrap();
+        assert_eq!(expanded.args, ["$HOME", r"''\''; rm -rf ~; echo '\'''", "| wc -c"]);
+
+        let mut search = deploy();
+        search.params.clear();
+        search.working_dir = None;
+        search.args = vec!["{query}".to_string(), "{date:%Q}".to_string()];
+        let invocation = Invocation::new(&search, "rust lifetimes");
+        assert!(invocation.is_complete());
+        assert!(invocation.expand(&search, &Clipboard, now).is_err());
+
+        // Variables and placeholders do not expand each other's values
+        search.args = vec!["$INJECT".to_string(), "{query}".to_string(), "${TAG:-{query}}".to_string()];
+        let invocation = Invocation::new(&search, "$HOME");
+        let expanded = invocation.expand(&search, &Clipboard, now).unwrap();
+        assert_eq!(expanded.args, ["{clipboard}", "$HOME", "$HOME"]);
+    }
+}
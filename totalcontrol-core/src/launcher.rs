@@ -0,0 +1,837 @@
+// launcher.rs
+use crate::action::Action;
+use crate::executor::{Executor, SystemExecutor};
+use crate::history::History;
+use crate::invocation::{takes_input, Invocation};
+use crate::process::{ProcessHandle, ProcessOutcome, StopReason};
+use crate::provider::{BuiltinProvider, SuggestionProvider};
+use crate::registry::{self, category_suggestions};
+use crate::suggestion::Suggestion;
+use crate::websearch::{self, default_engine, keyword_search};
+use chrono::Local;
+use command_config_parser::{fuzzy_match, risks, Command, CommandRegistry, DangerLevel, HotkeyAction};
+use std::cell::RefCell;
+use std::io;
+use std::time::Duration;
+
+// How many fuzzy-score points one recent launch is worth
+pub(crate) const FRECENCY_WEIGHT: f64 = 12.0;
+// Typed before a name to list running processes that can be stopped
+const KILL_KEYWORD: &str = "kill";
+// Typed before a filter to list the clipboard and snippets
+const CLIP_KEYWORD: &str = "clip";
+// Starts a query limited to one category, e.g. "@development"
+const CATEGORY_PREFIX: char = '@';
+// Typed before a query to see how it would resolve instead of running it
+const EXPLAIN_KEYWORD: &str = "explain";
+
+/// Ties suggestion providers and an executor together. Frontends only
+/// render what [`Launcher::suggest`] returns and hand the picked entry
+/// back to [`Launcher::execute`].
+pub struct Launcher<E: Executor = SystemExecutor> {
+    providers: Vec<Box<dyn SuggestionProvider>>,
+    registry: Option<CommandRegistry>,
+    history: RefCell<History>,
+    executor: E,
+    // Processes started by the launcher that have not been reported yet
+    running: RefCell<Vec<Running>>,
+    // Failures that stopped nothing, waiting for the frontend to log them
+    problems: RefCell<Vec<String>>,
+    // Clipboard text read once while the launcher is shown
+    clipboard: RefCell<Option<Option<String>>>,
+}
+
+struct Running {
+    handle: ProcessHandle,
+    show_output: bool,
+}
+
+/// A process started by the launcher that has exited.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finished {
+    pub outcome: ProcessOutcome,
+    // The command asked for its output to be shown even on success
+    pub show_output: bool,
+}
+
+impl Finished {
+    // Whether a frontend should bring this to the user's attention; a
+    // cancelled process only if its output was asked for
+    pub fn should_report(&self) -> bool {
+        let cancelled = self.outcome.stopped == Some(StopReason::Cancelled);
+        self.show_output || (!self.outcome.success && !cancelled)
+    }
+}
+
+/// What picking a suggestion would do, see [`Launcher::plan`].
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub query: String,
+    pub action: Action,
+    // The registry command behind the action, with every field expanded
+    pub command: Option<Command>,
+    // Set when the user has to agree before this runs
+    pub confirmation: Option<Confirmation>,
+}
+
+/// Why a plan needs the user's go-ahead, and the command line it would run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Confirmation {
+    pub command_line: String,
+    pub level: DangerLevel,
+    // Risky patterns found in the command line
+    pub reasons: Vec<String>,
+}
+
+impl Confirmation {
+    fn for_command(command: &Command) -> Option<Self> {
+        let confirmation = Self::for_text(&command.command_line(), command.danger_level);
+        if confirmation.is_none() && command.confirm {
+            return Some(Self {
+                command_line: command.command_line(),
+                level: command.danger_level.max(DangerLevel::Medium),
+                reasons: Vec::new(),
+            });
+        }
+        confirmation
+    }
+
+    // Risky text always counts as high danger
+    fn for_text(command_line: &str, level: DangerLevel) -> Option<Self> {
+        let reasons = risks(command_line);
+        let level = if reasons.is_empty() { level } else { DangerLevel::High };
+        (level > DangerLevel::Low).then(|| Self {
+            command_line: command_line.to_string(),
+            level,
+            reasons,
+        })
+    }
+}
+
+/// A process started by the launcher that is still running.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunningProcess {
+    pub label: String,
+    pub pid: u32,
+    pub elapsed: Duration,
+    // Cancelled or timed out, waiting for the process group to exit
+    pub stopping: bool,
+}
+
+impl Default for Launcher<SystemExecutor> {
+    fn default() -> Self {
+        let mut launcher = Self::with_executor(SystemExecutor);
+        launcher.add_provider(BuiltinProvider);
+        launcher
+    }
+}
+
+impl<E: Executor> Launcher<E> {
+    pub fn with_executor(executor: E) -> Self {
+        Self {
+            providers: Vec::new(),
+            registry: None,
+            history: RefCell::new(History::new()),
+            executor,
+            running: RefCell::new(Vec::new()),
+            problems: RefCell::new(Vec::new()),
+            clipboard: RefCell::new(None),
+        }
+    }
+
+    pub fn add_provider<P: SuggestionProvider + 'static>(&mut self, provider: P) {
+        self.providers.push(Box::new(provider));
+    }
+
+    pub fn set_registry(&mut self, registry: CommandRegistry) {
+        self.registry = Some(registry);
+    }
+
+    pub fn registry(&self) -> Option<&CommandRegistry> {
+        self.registry.as_ref()
+    }
+
+    pub fn set_history(&mut self, history: History) {
+        self.history = RefCell::new(history);
+    }
+
+    pub fn history(&self) -> std::cell::Ref<'_, History> {
+        self.history.borrow()
+    }
+
+    pub fn executor(&self) -> &E {
+        &self.executor
+    }
+
+    pub fn suggest(&self, query: &str) -> Vec<Suggestion> {
+        // "@category ..." lists only that category's commands
+        let scoped = query.strip_prefix(CATEGORY_PREFIX).map(|scoped| {
+            let (category, filter) = scoped.split_once(char::is_whitespace).unwrap_or((scoped, ""));
+            let registry = self.registry.iter();
+            registry.flat_map(|registry| category_suggestions(registry, category, filter.trim())).collect()
+        });
+
+        // User commands come before the built-in providers
+        let mut suggestions: Vec<Suggestion> = match scoped {
+            Some(suggestions) => suggestions,
+            None => self
+                .registry
+                .iter()
+                .map(|registry| registry as &dyn SuggestionProvider)
+                .chain(self.providers.iter().map(|provider| provider.as_ref()))
+                .flat_map(|provider| provider.suggestions(query))
+                .collect(),
+        };
+
+        // Blend in how often and how recently each item was launched
+        let history = self.history.borrow();
+        for suggestion in &mut suggestions {
+            suggestion.score += FRECENCY_WEIGHT * history.frecency(&suggestion.action.key(), query);
+        }
+
+        // Best match first; the sort is stable so ties keep provider order
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        // "kill ..." puts matching running processes first, "clip ..." the
+        // clipboard and snippets, and a search keyword its web search
+        let mut first = self.kill_suggestions(query);
+        first.append(&mut self.clip_suggestions(query));
+        if let Some((engine, terms)) = keyword_search(self.registry.as_ref(), query) {
+            first.push(websearch::search_suggestion(&engine, &terms));
+        }
+        if !first.is_empty() {
+            first.append(&mut suggestions);
+            suggestions = first;
+        }
+
+        if let Some((EXPLAIN_KEYWORD, rest)) = query.trim_start().split_once(char::is_whitespace) {
+            let rest = rest.trim();
+            if !rest.is_empty() {
+                suggestions.insert(
+                    0,
+                    Suggestion::new(format!("Explain \"{}\"", rest), Action::Explain(rest.to_string()))
+                        .with_subtitle("Show how this query resolves without running it")
+                        .with_icon("info"),
+                );
+            }
+        }
+
+        // If no matches, suggest running as command or searching the web
+        if suggestions.is_empty() && !query.is_empty() && !query.starts_with(CATEGORY_PREFIX) {
+            suggestions.push(
+                Suggestion::new(query, Action::ShellCommand(query.to_string()))
+                    .with_subtitle("Run command")
+                    .with_icon("terminal"),
+            );
+            if let Some(engine) = default_engine(self.registry.as_ref()) {
+                suggestions.push(websearch::search_suggestion(&engine, query.trim()));
+            }
+        }
+
+        suggestions
+    }
+
+    /// Parameter values for a picked registry command, taken from the words
+    /// typed after its name. Frontends prompt for [`Invocation::current`]
+    /// until it is complete, then call [`Launcher::execute_with`].
+    pub fn prepare(&self, query: &str, suggestion: &Suggestion) -> Option<Invocation> {
+        let Action::RegistryCommand(ref name) = suggestion.action else {
+            return None;
+        };
+        let command = self.registry.as_ref()?.get_command(name)?;
+
+        // Only text after the command's own name or alias is input for it
+        let query = query.trim();
+        let (word, rest) = query.split_once(char::is_whitespace).unwrap_or((query, ""));
+        let named = self
+            .registry
+            .as_ref()
+            .and_then(|registry| registry.get_command(word))
+            .is_some_and(|named| named.name == command.name);
+
+        Some(Invocation::new(command, if named { rest } else { "" }))
+    }
+
+    pub fn running(&self) -> Vec<RunningProcess> {
+        self.running
+            .borrow()
+            .iter()
+            .map(|running| RunningProcess {
+                label: running.handle.label().to_string(),
+                pid: running.handle.pid(),
+                elapsed: running.handle.elapsed(),
+                stopping: running.handle.is_stopping(),
+            })
+            .collect()
+    }
+
+    /// Stop a running process and everything it started. It is reported
+    /// by [`Launcher::poll_finished`] once it has exited.
+    pub fn cancel(&self, pid: u32) -> io::Result<()> {
+        let mut running = self.running.borrow_mut();
+        let process = running
+            .iter_mut()
+            .find(|running| running.handle.pid() == pid)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no running process {}", pid)))?;
+        process.handle.cancel()
+    }
+
+    fn kill_suggestions(&self, query: &str) -> Vec<Suggestion> {
+        // A registry command of the same name wins
+        if self.command_suggestion(KILL_KEYWORD).is_some() {
+            return Vec::new();
+        }
+        let filter = match query.trim_start().split_once(char::is_whitespace) {
+            Some((KILL_KEYWORD, filter)) => filter.trim(),
+            None if query.trim() == KILL_KEYWORD => "",
+            _ => return Vec::new(),
+        };
+
+        let mut suggestions: Vec<Suggestion> = self
+            .running()
+            .into_iter()
+            .filter(|process| !process.stopping)
+            .filter_map(|process| {
+                let score = if filter.is_empty() { 0 } else { fuzzy_match(filter, &process.label)?.score };
+                let subtitle = format!("Stop · running for {}s · pid {}", process.elapsed.as_secs(), process.pid);
+                Some(
+                    Suggestion::new(process.label, Action::CancelProcess(process.pid))
+                        .with_subtitle(subtitle)
+                        .with_icon("stop")
+                        .with_score(score as f64),
+                )
+            })
+            .collect();
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+        suggestions
+    }
+
+    // Text copied before, then snippets matching what follows "clip"
+    fn clip_suggestions(&self, query: &str) -> Vec<Suggestion> {
+        let filter = match query.trim_start().split_once(char::is_whitespace) {
+            Some((CLIP_KEYWORD, filter)) => filter.trim(),
+            None if query.trim() == CLIP_KEYWORD => "",
+            _ => return Vec::new(),
+        };
+
+        let mut suggestions = Vec::new();
+        // Copying it again keeps only the plain text
+        if filter.is_empty() {
+            let text = self
+                .clipboard
+                .borrow_mut()
+                .get_or_insert_with(|| self.executor.read_clipboard().ok())
+                .clone();
+            if let Some(text) = text {
+                if let Some(title) = text.lines().map(str::trim).find(|line| !line.is_empty()) {
+                    suggestions.push(
+                        Suggestion::new(title, Action::CopyText(text.clone()))
+                            .with_subtitle("Clipboard · copy as plain text")
+                            .with_icon("clipboard"),
+                    );
+                }
+            }
+        }
+
+        let snippets = self.registry.iter().flat_map(|registry| registry.snippets());
+        let mut matched: Vec<Suggestion> = snippets
+            .filter_map(|snippet| {
+                let score = if filter.is_empty() { 0 } else { fuzzy_match(filter, &snippet.name)?.score };
+                Some(
+                    Suggestion::new(&snippet.name, Action::CopyText(snippet.text.clone()))
+                        .with_subtitle(format!("Snippet · {}", snippet.text.lines().next().unwrap_or_default()))
+                        .with_icon("snippet")
+                        .with_score(score as f64),
+                )
+            })
+            .collect();
+        matched.sort_by(|a, b| b.score.total_cmp(&a.score));
+        suggestions.append(&mut matched);
+        suggestions
+    }
+
+    /// What to show in the launcher when a hotkey bound to `action` fires,
+    /// or `None` when it runs its command without asking for anything.
+    pub fn hotkey_query(&self, action: &HotkeyAction) -> Option<String> {
+        match action {
+            HotkeyAction::Launcher => Some(String::new()),
+            HotkeyAction::Category(category) => Some(format!("{}{} ", CATEGORY_PREFIX, category)),
+            HotkeyAction::Clipboard => Some(format!("{} ", CLIP_KEYWORD)),
+            HotkeyAction::Command(name) => {
+                let command = self.registry.as_ref()?.get_command(name)?;
+                takes_input(command).then(|| format!("{} ", name))
+            }
+        }
+    }
+
+    // A registry command as a suggestion, for running it by name
+    pub fn command_suggestion(&self, name: &str) -> Option<Suggestion> {
+        self.registry.as_ref()?.get_command(name).map(registry::suggestion)
+    }
+
+    /// Forgets what was read while the launcher was last shown, such as
+    /// the clipboard. Frontends call this each time they show it.
+    pub fn begin_session(&self) {
+        self.clipboard.take();
+    }
+
+    /// Problems since the last call that did not stop anything from
+    /// running, such as a history file that could not be written.
+    /// Frontends poll this to log them.
+    pub fn take_problems(&self) -> Vec<String> {
+        self.problems.take()
+    }
+
+    /// Processes that exited since the last call. Frontends poll this to
+    /// report failures and output. A process that can no longer be waited
+    /// on is dropped and reported through [`Launcher::take_problems`].
+    pub fn poll_finished(&self) -> Vec<Finished> {
+        let mut finished = Vec::new();
+        self.running.borrow_mut().retain_mut(|running| match running.handle.try_wait() {
+            Ok(Some(outcome)) => {
+                finished.push(Finished {
+                    outcome,
+                    show_output: running.show_output,
+                });
+                false
+            }
+            Ok(None) => true,
+            Err(err) => {
+                let problem = format!("Lost track of '{}': {}", running.handle.label(), err);
+                self.problems.borrow_mut().push(problem);
+                false
+            }
+        });
+        finished
+    }
+
+    /// Resolve a picked suggestion into exactly what would run, without
+    /// running it. Registry commands come back fully expanded.
+    pub fn plan(&self, query: &str, suggestion: &Suggestion, invocation: Option<&Invocation>) -> io::Result<Plan> {
+        let (command, confirmation) = match suggestion.action {
+            Action::RegistryCommand(ref name) => {
+                let command = self
+                    .registry
+                    .as_ref()
+                    .and_then(|registry| registry.get_command(name))
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("unknown command '{}'", name),
+                        )
+                    })?;
+                let expanded = match invocation {
+                    Some(invocation) => invocation.expand(command, &self.executor, Local::now())?,
+                    None => Invocation::new(command, "").expand(command, &self.executor, Local::now())?,
+                };
+                let confirmation = Confirmation::for_command(&expanded);
+                (Some(expanded), confirmation)
+            }
+            // Typed straight into the launcher, so only the text is checked
+            Action::ShellCommand(ref text) => (None, Confirmation::for_text(text, DangerLevel::Low)),
+            _ => (None, None),
+        };
+
+        Ok(Plan {
+            query: query.to_string(),
+            action: suggestion.action.clone(),
+            command,
+            confirmation,
+        })
+    }
+
+    // Run the suggestion picked for `query` and record it in the history
+    pub fn execute(&self, query: &str, suggestion: &Suggestion) -> io::Result<()> {
+        let invocation = self.prepare(query, suggestion);
+        self.execute_with(query, suggestion, invocation.as_ref())
+    }
+
+    /// As `execute`, with parameter values already gathered. Anything that
+    /// needs confirmation is refused; frontends show [`Plan::confirmation`]
+    /// and then call [`Launcher::run`].
+    pub fn execute_with(&self, query: &str, suggestion: &Suggestion, invocation: Option<&Invocation>) -> io::Result<()> {
+        let plan = self.plan(query, suggestion, invocation)?;
+        if plan.confirmation.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("'{}' needs confirmation before it runs", suggestion.title),
+            ));
+        }
+        self.run(&plan)
+    }
+
+    // Carry out a plan as it is, confirmed or not
+    pub fn run(&self, plan: &Plan) -> io::Result<()> {
+        match (&plan.action, &plan.command) {
+            (_, Some(command)) => {
+                let handle = self.executor.run_command(command)?;
+                self.track(handle, command.show_output);
+            }
+            (Action::CancelProcess(pid), None) => {
+                // Stopping a process is not a launch worth remembering
+                return self.cancel(*pid);
+            }
+            (Action::Explain(query), None) => {
+                // Frontends show `explain` themselves; nothing runs either way
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("'{}' is explained, not run", query),
+                ));
+            }
+            (action, None) => {
+                let handle = self.executor.execute(action)?;
+                self.track(handle, false);
+            }
+        }
+
+        if let Err(err) = self.history.borrow_mut().record(&plan.query, &plan.action.key()) {
+            self.problems.borrow_mut().push(format!("Failed to record launch history: {}", err));
+        }
+
+        Ok(())
+    }
+
+    fn track(&self, handle: Option<ProcessHandle>, show_output: bool) {
+        if let Some(handle) = handle {
+            self.running.borrow_mut().push(Running { handle, show_output });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    use command_config_parser::create_sample_config;
+    use std::fs;
+    use tempfile::NamedTempFile;
+
+    #[derive(Default)]
+    struct RecordingExecutor {
+        executed: RefCell<Vec<Action>>,
+        commands: RefCell<Vec<String>>,
+        clipboard_reads: Cell<usize>,
+    }
+
+    impl Executor for RecordingExecutor {
+        fn execute(&self, action: &Action) -> io::Result<Option<ProcessHandle>> {
+            self.executed.borrow_mut().push(action.clone());
+            Ok(None)
+        }
+
+        fn run_command(&self, command: &command_config_parser::Command) -> io::Result<Option<ProcessHandle>> {
+            let line = format!("{} {}", command.executable, command.args.join(" "));
+            self.commands.borrow_mut().push(line);
+            Ok(None)
+        }
+
+        fn read_clipboard(&self) -> io::Result<String> {
+            self.clipboard_reads.set(self.clipboard_reads.get() + 1);
+            Ok("copied text".to_string())
+        }
+    }
+
+    fn test_launcher() -> Launcher<RecordingExecutor> {
+        let mut launcher = Launcher::with_executor(RecordingExecutor::default());
+        launcher.add_provider(BuiltinProvider);
+        launcher
+    }
+
+    #[test]
+    fn test_suggest_matches_builtins() {
+        let launcher = test_launcher();
+
+        let suggestions = launcher.suggest("calc");
+        assert_eq!(suggestions[0].title, "calc");
+        assert_eq!(suggestions[0].action, Action::LaunchExecutable("calc.exe".to_string()));
+        assert!(suggestions.iter().any(|s| s.title == "calculator"));
+
+        let github = launcher.suggest("GIT");
+        assert_eq!(github[0].action, Action::OpenUrl("https://www.github.com".to_string()));
+
+        // Fuzzy acronym match, with highlight ranges for the frontend
+        let task = launcher.suggest("tm");
+        assert_eq!(task[0].title, "taskmanager");
+        assert_eq!(task[0].highlights, vec![0..1, 4..5]);
+    }
+
+    #[test]
+    fn test_suggest_falls_back_to_run() {
+        let launcher = test_launcher();
+
+        let suggestions = launcher.suggest("echo hi");
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].title, "echo hi");
+        assert_eq!(suggestions[0].action, Action::ShellCommand("echo hi".to_string()));
+        // Then the default search engine
+        assert_eq!(
+            suggestions[1].action,
+            Action::OpenUrl("https://www.google.com/search?q=echo%20hi".to_string())
+        );
+        assert!(launcher.suggest("").len() > 1);
+
+        // A search keyword goes first, whatever else the query matches
+        let search = launcher.suggest("g rust lifetimes");
+        assert_eq!(search[0].title, "Search Google for \"rust lifetimes\"");
+        assert_eq!(
+            search[0].action,
+            Action::OpenUrl("https://www.google.com/search?q=rust%20lifetimes".to_string())
+        );
+    }
+
+    #[test]
+    fn test_aliases_shadow_search_keywords() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("commands.json");
+        create_sample_config(&path).unwrap();
+        let mut config: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        config["commands"][0]["args"] = serde_json::json!(["build", "{query}"]);
+        config["commands"][0]["aliases"] = serde_json::json!(["b", "r"]);
+        config["engines"] = serde_json::json!([{ "keyword": "b", "name": "Bing", "url": "https://www.bing.com/search?q={query}" }]);
+        fs::write(&path, config.to_string()).unwrap();
+
+        let mut launcher = test_launcher();
+        launcher.set_registry(CommandRegistry::load_from_file(&path).unwrap());
+
+        // An alias wins over the built-in keyword it spells
+        let titles: Vec<String> = launcher.suggest("r release").into_iter().map(|s| s.title).collect();
+        assert_eq!(titles[0], "build");
+        assert!(titles.iter().all(|title| !title.starts_with("Search Reddit")));
+
+        // but not over a keyword configured on purpose
+        assert_eq!(launcher.suggest("b release")[0].title, "Search Bing for \"release\"");
+    }
+
+    #[test]
+    fn test_execute_dispatches_resolved_action() {
+        let launcher = test_launcher();
+
+        let paint = launcher.suggest("paint").remove(0);
+        launcher.execute("paint", &paint).unwrap();
+        assert_eq!(
+            launcher.executor().executed.borrow().as_slice(),
+            &[Action::LaunchExecutable("mspaint.exe".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_registry_commands() {
+        let temp_file = NamedTempFile::new().unwrap();
+        create_sample_config(temp_file.path()).unwrap();
+
+        let mut launcher = test_launcher();
+        launcher.set_registry(CommandRegistry::load_from_file(temp_file.path()).unwrap());
+
+        // Matched through the "prod" alias, listed ahead of built-ins
+        let suggestions = launcher.suggest("prod");
+        assert_eq!(suggestions[0].title, "deploy");
+        assert_eq!(suggestions[0].subtitle, "Deploy to production [deployment]");
+
+        // Production deploys ask first, showing what will run
+        let err = launcher.execute("prod", &suggestions[0]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(launcher.executor().commands.borrow().is_empty());
+
+        let plan = launcher.plan("prod", &suggestions[0], None).unwrap();
+        let confirmation = plan.confirmation.as_ref().unwrap();
+        assert_eq!(confirmation.level, DangerLevel::High);
+        assert!(confirmation.command_line.starts_with("bash -c "));
+        launcher.run(&plan).unwrap();
+        assert_eq!(
+            launcher.executor().commands.borrow().as_slice(),
+            &["bash -c echo 'Deploying...'".to_string()]
+        );
+        assert!(launcher.executor().executed.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_hotkey_actions() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("commands.json");
+        create_sample_config(&path).unwrap();
+        let mut config: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        config["snippets"] = serde_json::json!([
+            { "name": "email", "text": "ada@example.com" },
+            { "name": "address", "text": "1 Main St\nSpringfield" },
+        ]);
+        config["commands"][0]["args"] = serde_json::json!(["build", "{query}"]);
+        fs::write(&path, config.to_string()).unwrap();
+
+        let mut launcher = test_launcher();
+        launcher.set_registry(CommandRegistry::load_from_file(&path).unwrap());
+
+        // Category hotkeys open on that category alone
+        let query = launcher.hotkey_query(&HotkeyAction::Category("Development".to_string())).unwrap();
+        assert_eq!(query, "@Development ");
+        let titles: Vec<String> = launcher.suggest(&query).into_iter().map(|s| s.title).collect();
+        assert_eq!(titles, ["build", "test"]);
+        assert_eq!(launcher.suggest("@development tst")[0].title, "test");
+        assert!(launcher.suggest("@development xyz").is_empty());
+
+        let query = launcher.hotkey_query(&HotkeyAction::Clipboard).unwrap();
+        let titles: Vec<String> = launcher.suggest(&query).into_iter().map(|s| s.title).collect();
+        assert_eq!(titles, ["copied text", "email", "address"]);
+        let address = &launcher.suggest("clip addr")[0];
+        assert_eq!(address.action, Action::CopyText("1 Main St\nSpringfield".to_string()));
+        assert_eq!(address.subtitle, "Snippet · 1 Main St");
+        // The clipboard is read once per session, and not while filtering
+        launcher.suggest(&query);
+        assert_eq!(launcher.executor().clipboard_reads.get(), 1);
+        launcher.begin_session();
+        launcher.suggest(&query);
+        assert_eq!(launcher.executor().clipboard_reads.get(), 2);
+
+        // Commands run straight away unless they take input
+        assert_eq!(launcher.hotkey_query(&HotkeyAction::Command("test".to_string())), None);
+        assert_eq!(launcher.hotkey_query(&HotkeyAction::Command("build".to_string())).as_deref(), Some("build "));
+        let test = launcher.command_suggestion("test").unwrap();
+        launcher.execute("test", &test).unwrap();
+        assert_eq!(launcher.executor().commands.borrow().as_slice(), &["cargo test".to_string()]);
+    }
+
+    #[test]
+    fn test_risky_text_needs_confirmation() {
+        let launcher = test_launcher();
+
+        let wipe = launcher.suggest("rm -rf ~/projects").remove(0);
+        let plan = launcher.plan("rm -rf ~/projects", &wipe, None).unwrap();
+        let confirmation = plan.confirmation.unwrap();
+        assert_eq!(confirmation.command_line, "rm -rf ~/projects");
+        assert_eq!(confirmation.reasons, ["deletes files recursively without asking (`rm -rf`)"]);
+        assert!(launcher.execute("rm -rf ~/projects", &wipe).is_err());
+
+        let echo = launcher.suggest("echo hi").remove(0);
+        assert!(launcher.plan("echo hi", &echo, None).unwrap().confirmation.is_none());
+        assert!(launcher.executor().executed.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_registry_command_parameters() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("commands.json");
+        fs::write(
+            &path,
+            r#"{ "version": "1.0", "commands": [
+                { "name": "deploy", "description": "", "executable": "deploy.sh",
+                  "args": ["{env}", "{note}"], "aliases": ["dp"],
+                  "params": [{ "name": "env", "type": "choice", "choices": ["staging", "prod"] }] },
+                { "name": "ship it now", "description": "", "executable": "ship.sh", "args": ["{note}"], "aliases": ["sh"] }
+            ] }"#,
+        )
+        .unwrap();
+
+        let mut launcher = test_launcher();
+        launcher.set_registry(CommandRegistry::load_from_file(&path).unwrap());
+
+        // Typed after the alias, listed even though the query is not a name
+        let suggestions = launcher.suggest("dp prod hotfix 2");
+        assert_eq!(suggestions[0].title, "deploy");
+        launcher.execute("dp prod hotfix 2", &suggestions[0]).unwrap();
+        // Listed once, even when the whole query also fuzzy-matches it
+        let titles: Vec<String> = launcher.suggest("sh now").into_iter().map(|s| s.title).collect();
+        assert_eq!(titles.iter().filter(|title| *title == "ship it now").count(), 1);
+
+        // Picked by fuzzy match: nothing typed counts as input
+        let suggestion = launcher.suggest("depl").remove(0);
+        let mut invocation = launcher.prepare("depl", &suggestion).unwrap();
+        assert!(launcher.execute("depl", &suggestion).is_err());
+        invocation.answer("staging").unwrap();
+        invocation.answer("first").unwrap();
+        launcher.execute_with("depl", &suggestion, Some(&invocation)).unwrap();
+
+        assert_eq!(
+            launcher.executor().commands.borrow().as_slice(),
+            &["deploy.sh prod hotfix 2".to_string(), "deploy.sh staging first".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_reports_finished_processes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("commands.json");
+        let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+        fs::write(
+            &path,
+            serde_json::json!({ "version": "1.0", "commands": [
+                { "name": "fail", "description": "", "executable": shell, "args": [flag, "echo oops 1>&2&& exit 2"] },
+                { "name": "hello", "description": "", "executable": shell, "args": [flag, "echo hi"], "show_output": true },
+            ] })
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut launcher = Launcher::with_executor(SystemExecutor);
+        launcher.set_registry(CommandRegistry::load_from_file(&path).unwrap());
+        for name in ["fail", "hello"] {
+            let suggestion = launcher.suggest(name).remove(0);
+            launcher.execute(name, &suggestion).unwrap();
+        }
+        assert_eq!(launcher.running().len(), 2);
+
+        let mut finished = Vec::new();
+        while finished.len() < 2 {
+            finished.extend(launcher.poll_finished());
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        finished.sort_by(|a, b| a.outcome.label.cmp(&b.outcome.label));
+        assert_eq!(finished[0].outcome.exit_code, Some(2));
+        assert_eq!(finished[0].outcome.stderr.trim(), "oops");
+        assert_eq!(finished[1].outcome.stdout.trim(), "hi");
+        assert!(finished.iter().all(Finished::should_report));
+        assert!(launcher.running().is_empty());
+
+        // Listed under "kill" and stopped on request, without a report
+        let sleep = Suggestion::new("sleep", Action::ShellCommand(if cfg!(windows) { "ping -n 30 127.0.0.1" } else { "sleep 30" }.to_string()));
+        launcher.execute("sleep", &sleep).unwrap();
+        let kill = launcher.suggest("kill").remove(0);
+        assert!(matches!(kill.action, Action::CancelProcess(_)));
+        launcher.execute("kill", &kill).unwrap();
+        assert!(launcher.running()[0].stopping);
+        assert!(launcher.suggest("kill").iter().all(|s| !matches!(s.action, Action::CancelProcess(_))));
+        let cancelled = loop {
+            if let Some(finished) = launcher.poll_finished().pop() {
+                break finished;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        assert_eq!(cancelled.outcome.stopped, Some(StopReason::Cancelled));
+        assert!(!cancelled.should_report());
+        assert!(launcher.history().records().iter().all(|r| !r.key.starts_with("cancel:")));
+
+        // A registry command named `kill` takes the keyword over
+        fs::write(
+            &path,
+            r#"{ "version": "1.0", "commands": [
+                { "name": "kill", "description": "", "executable": "kill", "args": [] }
+            ] }"#,
+        )
+        .unwrap();
+        launcher.set_registry(CommandRegistry::load_from_file(&path).unwrap());
+        launcher.execute("sleep", &sleep).unwrap();
+        assert!(launcher.suggest("kill").iter().all(|s| !matches!(s.action, Action::CancelProcess(_))));
+        launcher.cancel(launcher.running()[0].pid).unwrap();
+
+        // Spawn errors come back from execute
+        let missing = Suggestion::new("x", Action::LaunchExecutable("definitely-not-a-program-42".to_string()));
+        assert!(launcher.execute("x", &missing).is_err());
+    }
+
+    #[test]
+    fn test_history_boosts_ranking() {
+        let mut launcher = test_launcher();
+        assert_eq!(launcher.suggest("c")[0].title, "calc");
+
+        let mut history = History::new();
+        history.record("c", "exe:cmd.exe").unwrap();
+        launcher.set_history(history);
+        assert_eq!(launcher.suggest("c")[0].title, "cmd");
+
+        // Executing records the launch
+        let firefox = launcher.suggest("firefox").remove(0);
+        launcher.execute("fire", &firefox).unwrap();
+        let history = launcher.history();
+        let last = history.records().last().unwrap();
+        assert_eq!((last.query.as_str(), last.key.as_str()), ("fire", "exe:firefox.exe"));
+    }
+}
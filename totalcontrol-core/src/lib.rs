@@ -0,0 +1,35 @@
+// totalcontrol-core: query → candidates → action resolution → execution,
+// independent of any particular windowing toolkit. Hotkey backends and
+// process handling are per platform; see `hotkey` and `process`.
+pub mod action;
+pub mod chord;
+pub mod executor;
+pub mod explain;
+pub mod history;
+pub mod hotkey;
+pub mod invocation;
+pub mod launcher;
+pub mod process;
+pub mod provider;
+pub mod registry;
+pub mod suggestion;
+pub mod websearch;
+
+pub use action::Action;
+pub use chord::{ChordHint, ChordMachine, ChordStep};
+pub use executor::{CommandEnv, Executor, SystemExecutor};
+pub use explain::{Candidate, Explanation};
+pub use history::History;
+pub use hotkey::{select_backend, HotkeyBackend, HotkeyError, HotkeyManager};
+pub use invocation::Invocation;
+pub use launcher::{Confirmation, Finished, Launcher, Plan, RunningProcess};
+pub use process::{ProcessHandle, ProcessOutcome, StopReason};
+pub use provider::{BuiltinProvider, SuggestionProvider};
+pub use suggestion::Suggestion;
+pub use websearch::{encode_query, search_url};
+
+pub use command_config_parser::search::candidate_paths;
+pub use command_config_parser::{
+    Command, CommandRegistry, ConfigError, ConfigWatcher, DangerLevel, HotkeyAction, HotkeyBinding, HotkeySpec, Key, KeySequence, KillSignal,
+    Param, ParamKind, SearchEngine, SequenceBinding,
+};
@@ -0,0 +1,480 @@
+// process.rs
+use command_config_parser::KillSignal;
+use std::io::{self, Read};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Output kept per stream; the oldest output is dropped first
+const MAX_CAPTURE: usize = 64 * 1024;
+// How long a finished process's output may still be arriving before
+// `try_wait` reports it anyway
+const SETTLE_TIME: Duration = Duration::from_millis(100);
+// How long `wait` lets the readers drain once the process has exited
+const DRAIN_TIME: Duration = Duration::from_secs(1);
+// How long a stopped process group gets before it is killed outright
+const KILL_GRACE: Duration = Duration::from_secs(3);
+
+/// A process started by an executor, with its output captured in the
+/// background until it exits. The process leads its own process group
+/// (a job object on Windows), so stopping it also stops anything it
+/// started.
+#[derive(Debug)]
+pub struct ProcessHandle {
+    label: String,
+    child: Child,
+    group: Group,
+    started: Instant,
+    timeout: Option<Duration>,
+    kill_signal: KillSignal,
+    // Why and when the process was asked to stop
+    stopping: Option<(StopReason, Instant)>,
+    // Whether the group was killed after ignoring the stop
+    killed: bool,
+    // Set once the process has exited, while its output settles
+    exited: Option<(ExitStatus, Instant)>,
+    stdout: Capture,
+    stderr: Capture,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Cancelled,
+    TimedOut,
+}
+
+/// How a process ended.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessOutcome {
+    pub label: String,
+    pub pid: u32,
+    // None when the process was ended by a signal
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    // Set when the launcher stopped the process
+    pub stopped: Option<StopReason>,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration: Duration,
+}
+
+impl ProcessHandle {
+    /// Start `cmd` with stdin closed and stdout/stderr captured. Spawn
+    /// errors name the program that could not be started.
+    pub fn spawn(label: impl Into<String>, mut cmd: Command) -> io::Result<Self> {
+        cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+        // Nothing runs until the process is in its job, so whatever it
+        // starts is in the job too
+        #[cfg(windows)]
+        std::os::windows::process::CommandExt::creation_flags(
+            &mut cmd,
+            windows::Win32::System::Threading::CREATE_SUSPENDED.0,
+        );
+
+        let mut child = cmd.spawn().map_err(|err| {
+            let program = cmd.get_program().to_string_lossy().into_owned();
+            io::Error::new(err.kind(), format!("failed to start `{}`: {}", program, err))
+        })?;
+        let group = Group::adopt(&child);
+        #[cfg(windows)]
+        if let Err(err) = resume(child.id()) {
+            child.kill().ok();
+            return Err(err);
+        }
+
+        let stdout = Capture::start(child.stdout.take());
+        let stderr = Capture::start(child.stderr.take());
+        Ok(Self {
+            label: label.into(),
+            child,
+            group,
+            started: Instant::now(),
+            timeout: None,
+            kill_signal: KillSignal::default(),
+            stopping: None,
+            killed: false,
+            exited: None,
+            stdout,
+            stderr,
+        })
+    }
+
+    // Stop the process once it has run this long; checked by `try_wait`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_kill_signal(mut self, signal: KillSignal) -> Self {
+        self.kill_signal = signal;
+        self
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    // Output captured so far
+    pub fn stdout(&self) -> String {
+        self.stdout.text()
+    }
+
+    pub fn stderr(&self) -> String {
+        self.stderr.text()
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.is_some()
+    }
+
+    /// Ask the process group to stop with the configured signal. If it is
+    /// still running [`KILL_GRACE`] later, `try_wait` kills it.
+    pub fn cancel(&mut self) -> io::Result<()> {
+        self.stop(StopReason::Cancelled)
+    }
+
+    fn stop(&mut self, reason: StopReason) -> io::Result<()> {
+        if self.stopping.is_some() {
+            return Ok(());
+        }
+        self.stopping = Some((reason, Instant::now()));
+        self.group.signal(self.pid(), self.kill_signal)
+    }
+
+    /// The outcome once the process has exited and its output has been
+    /// read, without blocking. Also enforces the timeout and kills groups
+    /// that ignored being stopped, so running processes should be polled
+    /// regularly.
+    pub fn try_wait(&mut self) -> io::Result<Option<ProcessOutcome>> {
+        if let Some((status, ended)) = self.exited {
+            return Ok(self.settled(status, ended));
+        }
+
+        if self.timeout.is_some_and(|timeout| self.started.elapsed() >= timeout) {
+            self.stop(StopReason::TimedOut)?;
+        }
+        if let Some((_, since)) = self.stopping {
+            if !self.killed && since.elapsed() >= KILL_GRACE {
+                self.group.signal(self.pid(), KillSignal::Kill)?;
+                self.killed = true;
+            }
+        }
+
+        match self.child.try_wait()? {
+            Some(status) => {
+                let ended = Instant::now();
+                self.exited = Some((status, ended));
+                Ok(self.settled(status, ended))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // The outcome once the readers have caught up with the exited process,
+    // or once they have had SETTLE_TIME to
+    fn settled(&mut self, status: ExitStatus, ended: Instant) -> Option<ProcessOutcome> {
+        let read = self.stdout.is_done() && self.stderr.is_done();
+        if !read && ended.elapsed() < SETTLE_TIME {
+            return None;
+        }
+        self.stdout.finish(Duration::ZERO);
+        self.stderr.finish(Duration::ZERO);
+        Some(self.outcome(status, ended))
+    }
+
+    pub fn wait(mut self) -> io::Result<ProcessOutcome> {
+        let status = self.child.wait()?;
+        let ended = Instant::now();
+        // Let the readers drain what the process wrote before exiting
+        self.stdout.finish(DRAIN_TIME);
+        self.stderr.finish(DRAIN_TIME);
+        Ok(self.outcome(status, ended))
+    }
+
+    fn outcome(&self, status: ExitStatus, ended: Instant) -> ProcessOutcome {
+        let stopped = self.stopping.map(|(reason, _)| reason);
+        ProcessOutcome {
+            label: self.label.clone(),
+            pid: self.pid(),
+            exit_code: status.code(),
+            success: status.success() && stopped.is_none(),
+            stopped,
+            stdout: self.stdout.text(),
+            stderr: self.stderr.text(),
+            duration: ended.duration_since(self.started),
+        }
+    }
+}
+
+impl ProcessOutcome {
+    // One line for a notification, e.g. "build exited with code 101 after 2.3s"
+    pub fn summary(&self) -> String {
+        let status = match (self.stopped, self.exit_code) {
+            (Some(StopReason::TimedOut), _) => "timed out".to_string(),
+            (Some(StopReason::Cancelled), _) => "was cancelled".to_string(),
+            (None, Some(0)) => "finished".to_string(),
+            (None, Some(code)) => format!("exited with code {}", code),
+            (None, None) => "was terminated".to_string(),
+        };
+        format!("{} {} after {:.1}s", self.label, status, self.duration.as_secs_f64())
+    }
+}
+
+// The process group on Unix, where the child's PID is the group ID
+#[cfg(unix)]
+#[derive(Debug)]
+struct Group;
+
+#[cfg(unix)]
+impl Group {
+    fn adopt(_: &Child) -> Self {
+        Group
+    }
+
+    fn signal(&self, pid: u32, signal: KillSignal) -> io::Result<()> {
+        let signal = match signal {
+            KillSignal::Term => libc::SIGTERM,
+            KillSignal::Int => libc::SIGINT,
+            KillSignal::Hup => libc::SIGHUP,
+            KillSignal::Kill => libc::SIGKILL,
+        };
+        // SAFETY: killpg only sends a signal; the group is ours
+        if unsafe { libc::killpg(pid as libc::pid_t, signal) } == 0 {
+            return Ok(());
+        }
+        match io::Error::last_os_error() {
+            // The whole group already exited
+            err if err.raw_os_error() == Some(libc::ESRCH) => Ok(()),
+            err => Err(err),
+        }
+    }
+}
+
+// A job object holding the child and everything it starts. Windows has no
+// signals to ask politely, so stopping ends the job straight away.
+#[cfg(windows)]
+#[derive(Debug)]
+struct Group {
+    job: Option<windows::Win32::Foundation::HANDLE>,
+}
+
+#[cfg(windows)]
+impl Group {
+    fn adopt(child: &Child) -> Self {
+        use std::os::windows::io::AsRawHandle;
+        use windows::Win32::Foundation::{CloseHandle, HANDLE};
+        use windows::Win32::System::JobObjects::{AssignProcessToJobObject, CreateJobObjectW};
+
+        // SAFETY: the job handle is closed on drop; the process handle is
+        // owned by `child`, which outlives this call
+        let job = unsafe {
+            CreateJobObjectW(None, windows::core::PCWSTR::null()).ok().filter(|&job| {
+                let assigned = AssignProcessToJobObject(job, HANDLE(child.as_raw_handle() as isize)).as_bool();
+                if !assigned {
+                    CloseHandle(job);
+                }
+                assigned
+            })
+        };
+        if job.is_none() {
+            eprintln!("[ERROR] Could not create a job for pid {}; only it will be stopped", child.id());
+        }
+        Group { job }
+    }
+
+    fn signal(&self, pid: u32, _: KillSignal) -> io::Result<()> {
+        use windows::Win32::System::JobObjects::TerminateJobObject;
+
+        match self.job {
+            // SAFETY: the handle is valid until drop
+            Some(job) if unsafe { TerminateJobObject(job, 1) }.as_bool() => Ok(()),
+            Some(_) => Err(io::Error::last_os_error()),
+            None => std::process::Command::new("taskkill")
+                .args(["/F", "/T", "/PID", &pid.to_string()])
+                .output()
+                .map(drop),
+        }
+    }
+}
+
+// Resume a process started suspended; std keeps the main thread's handle
+// to itself, so the process's threads are looked up
+#[cfg(windows)]
+fn resume(pid: u32) -> io::Result<()> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32,
+    };
+    use windows::Win32::System::Threading::{OpenThread, ResumeThread, THREAD_SUSPEND_RESUME};
+
+    let mut resumed = false;
+    // SAFETY: every handle opened here is closed before returning, and
+    // `entry` is sized as the snapshot functions expect
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0)?;
+        let mut entry = THREADENTRY32 {
+            dwSize: std::mem::size_of::<THREADENTRY32>() as u32,
+            ..Default::default()
+        };
+        let mut more = Thread32First(snapshot, &mut entry).as_bool();
+        while more {
+            if entry.th32OwnerProcessID == pid {
+                if let Ok(thread) = OpenThread(THREAD_SUSPEND_RESUME, false, entry.th32ThreadID) {
+                    resumed |= ResumeThread(thread) != u32::MAX;
+                    CloseHandle(thread);
+                }
+            }
+            more = Thread32Next(snapshot, &mut entry).as_bool();
+        }
+        CloseHandle(snapshot);
+    }
+    if !resumed {
+        return Err(io::Error::other(format!("could not resume process {}", pid)));
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+impl Drop for Group {
+    fn drop(&mut self) {
+        if let Some(job) = self.job.take() {
+            // SAFETY: closing the handle leaves the processes running
+            unsafe { windows::Win32::Foundation::CloseHandle(job) };
+        }
+    }
+}
+
+// Collects one output stream on a background thread. The thread is only
+// joined once it has finished, since a detached grandchild can keep the
+// pipe open long after the process itself exited.
+#[derive(Debug)]
+struct Capture {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    reader: Option<thread::JoinHandle<()>>,
+}
+
+impl Capture {
+    fn start<R: Read + Send + 'static>(stream: Option<R>) -> Self {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let reader = stream.map(|mut stream| {
+            let buffer = Arc::clone(&buffer);
+            thread::spawn(move || {
+                let mut chunk = [0u8; 4096];
+                while let Ok(n) = stream.read(&mut chunk) {
+                    if n == 0 {
+                        break;
+                    }
+                    let mut buffer = buffer.lock().unwrap_or_else(|e| e.into_inner());
+                    buffer.extend_from_slice(&chunk[..n]);
+                    if buffer.len() > MAX_CAPTURE {
+                        let excess = buffer.len() - MAX_CAPTURE;
+                        buffer.drain(..excess);
+                    }
+                }
+            })
+        });
+        Self { buffer, reader }
+    }
+
+    fn is_done(&self) -> bool {
+        self.reader.as_ref().is_none_or(|reader| reader.is_finished())
+    }
+
+    // Wait up to `limit` for the reader to reach the end of the stream;
+    // a reader still blocked after that is left running
+    fn finish(&mut self, limit: Duration) {
+        let deadline = Instant::now() + limit;
+        while !self.is_done() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        if self.is_done() {
+            if let Some(reader) = self.reader.take() {
+                reader.join().ok();
+            }
+        }
+    }
+
+    fn text(&self) -> String {
+        let buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        String::from_utf8_lossy(&buffer).into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str) -> Command {
+        let mut cmd = Command::new(if cfg!(windows) { "cmd" } else { "sh" });
+        cmd.args([if cfg!(windows) { "/C" } else { "-c" }, script]);
+        cmd
+    }
+
+    #[test]
+    fn test_captures_output_and_status() {
+        let handle = ProcessHandle::spawn("fail", shell("echo out&& echo err 1>&2&& exit 3")).unwrap();
+        assert!(handle.pid() > 0);
+
+        let outcome = handle.wait().unwrap();
+        assert_eq!(outcome.exit_code, Some(3));
+        assert!(!outcome.success);
+        assert_eq!(outcome.stdout.trim(), "out");
+        assert_eq!(outcome.stderr.trim(), "err");
+        assert!(outcome.summary().starts_with("fail exited with code 3 after "));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_timeout_stops_process_group() {
+        // The background sleep shares the group and must go with it
+        let mut handle = ProcessHandle::spawn("hang", shell("sleep 30 & echo $!; wait"))
+            .unwrap()
+            .with_timeout(Duration::from_millis(200));
+
+        let outcome = loop {
+            if let Some(outcome) = handle.try_wait().unwrap() {
+                break outcome;
+            }
+            thread::sleep(Duration::from_millis(20));
+        };
+        assert_eq!(outcome.stopped, Some(StopReason::TimedOut));
+        assert!(!outcome.success);
+        assert!(outcome.summary().starts_with("hang timed out after "));
+
+        // Gone, or a zombie waiting for init to reap it
+        let sleeper = outcome.stdout.trim();
+        thread::sleep(Duration::from_millis(100));
+        let state = std::fs::read_to_string(format!("/proc/{}/stat", sleeper)).unwrap_or_default();
+        assert!(state.is_empty() || state.contains(") Z "), "background sleep survived: {}", state);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wait_outlasts_open_pipe() {
+        // The background sleep inherits stdout and keeps it open
+        let started = Instant::now();
+        let outcome = ProcessHandle::spawn("detach", shell("sleep 5 & echo done")).unwrap().wait().unwrap();
+        assert!(started.elapsed() < Duration::from_secs(4));
+        assert_eq!(outcome.stdout.trim(), "done");
+        // SAFETY: only signals the test's own process group
+        unsafe { libc::killpg(outcome.pid as libc::pid_t, libc::SIGKILL) };
+    }
+
+    #[test]
+    fn test_spawn_error_names_program() {
+        let err = ProcessHandle::spawn("typo", Command::new("definitely-not-a-program-42")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(err.to_string().starts_with("failed to start `definitely-not-a-program-42`: "));
+    }
+}
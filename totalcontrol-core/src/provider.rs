@@ -0,0 +1,69 @@
+// provider.rs
+use crate::action::Action;
+use crate::suggestion::Suggestion;
+use command_config_parser::fuzzy_match;
+
+/// A source of launcher suggestions for a query.
+pub trait SuggestionProvider {
+    fn suggestions(&self, query: &str) -> Vec<Suggestion>;
+}
+
+// Common applications
+const APPS: &[(&str, &str)] = &[
+    ("notepad", "notepad.exe"),
+    ("calc", "calc.exe"),
+    ("calculator", "calc.exe"),
+    ("paint", "mspaint.exe"),
+    ("cmd", "cmd.exe"),
+    ("command", "cmd.exe"),
+    ("powershell", "powershell.exe"),
+    ("explorer", "explorer.exe"),
+    ("chrome", "chrome.exe"),
+    ("firefox", "firefox.exe"),
+    ("edge", "msedge.exe"),
+    ("task", "taskmgr.exe"),
+    ("taskmanager", "taskmgr.exe"),
+];
+
+// Common websites
+const WEBSITES: &[(&str, &str)] = &[
+    ("google", "https://www.google.com"),
+    ("youtube", "https://www.youtube.com"),
+    ("github", "https://www.github.com"),
+    ("stackoverflow", "https://stackoverflow.com"),
+    ("reddit", "https://www.reddit.com"),
+    ("twitter", "https://www.twitter.com"),
+    ("facebook", "https://www.facebook.com"),
+];
+
+/// The built-in list of common applications and websites.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BuiltinProvider;
+
+impl SuggestionProvider for BuiltinProvider {
+    fn suggestions(&self, query: &str) -> Vec<Suggestion> {
+        let apps = APPS.iter().filter_map(|(name, exe)| {
+            let m = fuzzy_match(query, name)?;
+            Some(
+                Suggestion::new(*name, Action::LaunchExecutable(exe.to_string()))
+                    .with_subtitle(*exe)
+                    .with_icon("application")
+                    .with_score(m.score as f64)
+                    .with_highlights(m.ranges),
+            )
+        });
+
+        let websites = WEBSITES.iter().filter_map(|(name, url)| {
+            let m = fuzzy_match(query, name)?;
+            Some(
+                Suggestion::new(*name, Action::OpenUrl(url.to_string()))
+                    .with_subtitle(*url)
+                    .with_icon("web")
+                    .with_score(m.score as f64)
+                    .with_highlights(m.ranges),
+            )
+        });
+
+        apps.chain(websites).collect()
+    }
+}
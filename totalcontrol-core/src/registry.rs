@@ -0,0 +1,62 @@
+// registry.rs
+use crate::action::Action;
+use crate::invocation::takes_input;
+use crate::provider::SuggestionProvider;
+use crate::suggestion::Suggestion;
+use command_config_parser::{fuzzy_match, Command, CommandRegistry};
+
+// User-defined commands from commands.json, matched by name or alias
+impl SuggestionProvider for CommandRegistry {
+    fn suggestions(&self, query: &str) -> Vec<Suggestion> {
+        let mut suggestions: Vec<Suggestion> = self
+            .search(query)
+            .into_iter()
+            .map(|(command, m)| suggestion(command).with_score(m.score as f64).with_highlights(m.ranges))
+            .collect();
+
+        // "deploy prod": a command name followed by its arguments, ranked as
+        // the name alone would be
+        if let Some((word, _)) = query.trim_start().split_once(char::is_whitespace) {
+            if let Some(command) = self.get_command(word).filter(|command| takes_input(command)) {
+                let score = self
+                    .search(word)
+                    .into_iter()
+                    .find(|(found, _)| found.name == command.name)
+                    .map_or(0, |(_, m)| m.score);
+                let named = suggestion(command).with_score(score as f64);
+                // The whole query may fuzzy-match the same command
+                suggestions.retain(|other| other.action.key() != named.action.key());
+                suggestions.insert(0, named);
+            }
+        }
+
+        suggestions
+    }
+}
+
+// Commands in `category` (any case) matching `filter`, for "@category ..."
+pub(crate) fn category_suggestions(registry: &CommandRegistry, category: &str, filter: &str) -> Vec<Suggestion> {
+    registry
+        .get_all_commands()
+        .iter()
+        .filter(|command| command.category.as_ref().is_some_and(|c| c.eq_ignore_ascii_case(category)))
+        .filter_map(|command| {
+            if filter.is_empty() {
+                return Some(suggestion(command));
+            }
+            let m = fuzzy_match(filter, &command.name)?;
+            Some(suggestion(command).with_score(m.score as f64).with_highlights(m.ranges))
+        })
+        .collect()
+}
+
+pub(crate) fn suggestion(command: &Command) -> Suggestion {
+    let subtitle = match command.category {
+        Some(ref category) => format!("{} [{}]", command.description, category),
+        None => command.description.clone(),
+    };
+
+    Suggestion::new(&command.name, Action::RegistryCommand(command.name.clone()))
+        .with_subtitle(subtitle)
+        .with_icon("command")
+}
@@ -0,0 +1,76 @@
+// suggestion.rs
+use crate::action::Action;
+use std::fmt;
+use std::ops::Range;
+
+/// A single entry in the launcher's result list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub title: String,
+    pub subtitle: String,
+    pub icon: Option<String>,
+    pub score: f64,
+    // Byte ranges of `title` matched by the query, for bolding
+    pub highlights: Vec<Range<usize>>,
+    pub action: Action,
+}
+
+impl Suggestion {
+    pub fn new(title: impl Into<String>, action: Action) -> Self {
+        Self {
+            title: title.into(),
+            subtitle: String::new(),
+            icon: None,
+            score: 0.0,
+            highlights: Vec::new(),
+            action,
+        }
+    }
+
+    pub fn with_subtitle(mut self, subtitle: impl Into<String>) -> Self {
+        self.subtitle = subtitle.into();
+        self
+    }
+
+    pub fn with_icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    pub fn with_score(mut self, score: f64) -> Self {
+        self.score = score;
+        self
+    }
+
+    pub fn with_highlights(mut self, highlights: Vec<Range<usize>>) -> Self {
+        self.highlights = highlights;
+        self
+    }
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.subtitle.is_empty() {
+            write!(f, "{}", self.title)
+        } else {
+            write!(f, "{} — {}", self.title, self.subtitle)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let plain = Suggestion::new("dir", Action::ShellCommand("dir".to_string()));
+        assert_eq!(plain.to_string(), "dir");
+
+        // Arrows in the command no longer matter, nothing is parsed back
+        let arrow = Suggestion::new("echo", Action::ShellCommand("echo a → b".to_string()))
+            .with_subtitle("echo a → b");
+        assert_eq!(arrow.to_string(), "echo — echo a → b");
+        assert_eq!(arrow.action, Action::ShellCommand("echo a → b".to_string()));
+    }
+}
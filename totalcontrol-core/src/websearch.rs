@@ -0,0 +1,102 @@
+// websearch.rs
+use crate::action::Action;
+use crate::suggestion::Suggestion;
+use command_config_parser::engines::QUERY_PLACEHOLDER;
+use command_config_parser::{CommandRegistry, SearchEngine};
+
+// Available without any configuration; a configured engine with the same
+// keyword replaces one of these
+const BUILTIN_ENGINES: &[(&str, &str, &str)] = &[
+    ("g", "Google", "https://www.google.com/search?q={query}"),
+    ("gh", "GitHub", "https://github.com/search?q={query}"),
+    ("yt", "YouTube", "https://www.youtube.com/results?search_query={query}"),
+    ("so", "Stack Overflow", "https://stackoverflow.com/search?q={query}"),
+    ("r", "Reddit", "https://www.reddit.com/search/?q={query}"),
+];
+// Searched when nothing matches and no engine is configured as the default
+const FALLBACK_KEYWORD: &str = "g";
+
+/// Percent-encode search terms for a URL. Everything but letters, digits
+/// and `-_.~` is encoded as UTF-8 bytes, spaces as `%20`.
+pub fn encode_query(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+pub fn search_url(engine: &SearchEngine, terms: &str) -> String {
+    engine.url.replace(QUERY_PLACEHOLDER, &encode_query(terms))
+}
+
+// The built-in engines, then the configured ones
+pub(crate) fn engines(registry: Option<&CommandRegistry>) -> Vec<SearchEngine> {
+    let configured = registry.map(CommandRegistry::engines).unwrap_or_default();
+    let builtin = BUILTIN_ENGINES
+        .iter()
+        .filter(|(keyword, _, _)| configured.iter().all(|engine| engine.keyword != *keyword))
+        .map(|(keyword, name, url)| SearchEngine {
+            keyword: keyword.to_string(),
+            name: Some(name.to_string()),
+            url: url.to_string(),
+            default: false,
+        });
+    builtin.chain(configured.iter().cloned()).collect()
+}
+
+pub(crate) fn default_engine(registry: Option<&CommandRegistry>) -> Option<SearchEngine> {
+    if let Some(engine) = registry.and_then(CommandRegistry::default_engine) {
+        return Some(engine.clone());
+    }
+    engines(registry).into_iter().find(|engine| engine.keyword == FALLBACK_KEYWORD)
+}
+
+// "gh totalcontrol" → the GitHub engine and "totalcontrol". A registry
+// command named or aliased like a built-in keyword wins over it.
+pub(crate) fn keyword_search(registry: Option<&CommandRegistry>, query: &str) -> Option<(SearchEngine, String)> {
+    let (keyword, terms) = query.trim_start().split_once(char::is_whitespace)?;
+    let terms = terms.trim();
+    if terms.is_empty() {
+        return None;
+    }
+    let engine = engines(registry).into_iter().find(|engine| engine.keyword == keyword)?;
+    let configured = registry.is_some_and(|registry| registry.engines().contains(&engine));
+    if !configured && registry.and_then(|registry| registry.get_command(keyword)).is_some() {
+        return None;
+    }
+    Some((engine, terms.to_string()))
+}
+
+pub(crate) fn search_suggestion(engine: &SearchEngine, terms: &str) -> Suggestion {
+    let url = search_url(engine, terms);
+    Suggestion::new(format!("Search {} for \"{}\"", engine.label(), terms), Action::OpenUrl(url.clone()))
+        .with_subtitle(url)
+        .with_icon("web")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_query() {
+        assert_eq!(encode_query("rust lifetimes"), "rust%20lifetimes");
+        assert_eq!(encode_query("a&b=c?d#e/f+g"), "a%26b%3Dc%3Fd%23e%2Ff%2Bg");
+        assert_eq!(encode_query("naïve ~_-."), "na%C3%AFve%20~_-.");
+    }
+
+    #[test]
+    fn test_keyword_search() {
+        let (engine, terms) = keyword_search(None, "  gh   totalcontrol core ").unwrap();
+        assert_eq!(engine.label(), "GitHub");
+        assert_eq!(search_url(&engine, &terms), "https://github.com/search?q=totalcontrol%20core");
+        assert!(keyword_search(None, "gh ").is_none());
+        assert!(keyword_search(None, "zz rust").is_none());
+        assert_eq!(default_engine(None).unwrap().keyword, "g");
+    }
+}